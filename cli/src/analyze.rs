@@ -0,0 +1,83 @@
+use crate::{Args, Error};
+use chip8_core::{Quirk, Syntax};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub(crate) struct Options {
+    rom: PathBuf,
+    dot: Option<PathBuf>,
+}
+
+impl Options {
+    pub(crate) fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut args = Args::new(args);
+        let mut rom = None;
+        let mut dot = None;
+
+        while let Some(arg) = args.next() {
+            match arg {
+                "--dot" => dot = Some(PathBuf::from(args.value(arg)?)),
+                _ if arg.starts_with("--") => {
+                    return Err(Error::Usage(format!("Unknown option {arg:?}")));
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(Error::Usage(format!("Unexpected argument {arg:?}"))),
+            }
+        }
+
+        Ok(Self {
+            rom: rom.ok_or_else(|| Error::Usage(String::from("Missing ROM path")))?,
+            dot,
+        })
+    }
+}
+
+pub(crate) fn analyze(options: &Options) -> Result<(), Error> {
+    let rom = fs::read(&options.rom).map_err(|err| Error::Io(options.rom.clone(), err))?;
+    let analysis = chip8_core::analyze(&rom);
+
+    let instructions: usize = analysis
+        .blocks()
+        .iter()
+        .map(|block| block.lines.len())
+        .sum();
+    println!(
+        "{instructions} reachable instructions in {} blocks",
+        analysis.blocks().len()
+    );
+
+    if analysis.quirk_uses().is_empty() {
+        println!("No quirk-sensitive instructions");
+    } else {
+        println!("Quirk-sensitive instructions:");
+        for quirk_use in analysis.quirk_uses() {
+            println!(
+                "  0x{:03X}  {:<20}  {}",
+                quirk_use.addr,
+                quirk_use.instruction.mnemonic(Syntax::Octo).to_string(),
+                quirk_use.quirk
+            );
+        }
+    }
+
+    let platform = analysis.suggested_platform();
+    println!("Suggested platform: {} ({platform})", platform.id());
+    println!("  based only on the opcode families used");
+    if !analysis.quirk_uses().is_empty() {
+        let quirks: Vec<&str> = analysis.quirks().into_iter().map(Quirk::name).collect();
+        println!(
+            "  check these quirks, which can't be inferred: {}",
+            quirks.join(", ")
+        );
+    }
+
+    if let Some(path) = &options.dot {
+        let file = File::create(path).map_err(|err| Error::Io(path.clone(), err))?;
+        analysis
+            .write_dot(BufWriter::new(file))
+            .map_err(|err| Error::Io(path.clone(), err))?;
+    }
+    Ok(())
+}
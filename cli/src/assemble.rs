@@ -0,0 +1,65 @@
+use crate::{Args, Error};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub(crate) struct Options {
+    source: PathBuf,
+    output: PathBuf,
+    symbols: Option<PathBuf>,
+}
+
+impl Options {
+    pub(crate) fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut args = Args::new(args);
+        let mut source = None;
+        let mut output = None;
+        let mut symbols = None;
+
+        while let Some(arg) = args.next() {
+            match arg {
+                "--output" => output = Some(PathBuf::from(args.value(arg)?)),
+                "--symbols" => symbols = Some(PathBuf::from(args.value(arg)?)),
+                _ if arg.starts_with("--") => {
+                    return Err(Error::Usage(format!("Unknown option {arg:?}")));
+                }
+                _ if source.is_none() => source = Some(PathBuf::from(arg)),
+                _ => return Err(Error::Usage(format!("Unexpected argument {arg:?}"))),
+            }
+        }
+
+        let source: PathBuf =
+            source.ok_or_else(|| Error::Usage(String::from("Missing source path")))?;
+        Ok(Self {
+            output: output.unwrap_or_else(|| source.with_extension("ch8")),
+            source,
+            symbols,
+        })
+    }
+}
+
+pub(crate) fn assemble(options: &Options) -> Result<(), Error> {
+    let source = fs::read_to_string(&options.source)
+        .map_err(|err| Error::Io(options.source.clone(), err))?;
+    let program = chip8_core::assemble(&source)
+        .map_err(|err| Error::Assemble(options.source.clone(), err))?;
+
+    fs::write(&options.output, &program.rom)
+        .map_err(|err| Error::Io(options.output.clone(), err))?;
+
+    if let Some(path) = &options.symbols {
+        let mut symbols = String::new();
+        for (name, addr) in &program.symbols {
+            let _ = writeln!(symbols, "0x{addr:03X} {name}");
+        }
+        fs::write(path, symbols).map_err(|err| Error::Io(path.clone(), err))?;
+    }
+
+    println!(
+        "Assembled {} bytes to {}",
+        program.rom.len(),
+        options.output.display()
+    );
+    Ok(())
+}
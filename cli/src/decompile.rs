@@ -0,0 +1,46 @@
+use crate::{Args, Error};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub(crate) struct Options {
+    rom: PathBuf,
+    output: Option<PathBuf>,
+}
+
+impl Options {
+    pub(crate) fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut args = Args::new(args);
+        let mut rom = None;
+        let mut output = None;
+
+        while let Some(arg) = args.next() {
+            match arg {
+                "--output" => output = Some(PathBuf::from(args.value(arg)?)),
+                _ if arg.starts_with("--") => {
+                    return Err(Error::Usage(format!("Unknown option {arg:?}")));
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(Error::Usage(format!("Unexpected argument {arg:?}"))),
+            }
+        }
+
+        Ok(Self {
+            rom: rom.ok_or_else(|| Error::Usage(String::from("Missing ROM path")))?,
+            output,
+        })
+    }
+}
+
+pub(crate) fn decompile(options: &Options) -> Result<(), Error> {
+    let rom = fs::read(&options.rom).map_err(|err| Error::Io(options.rom.clone(), err))?;
+    let source = chip8_core::decompile(&rom);
+
+    match &options.output {
+        Some(path) => fs::write(path, source).map_err(|err| Error::Io(path.clone(), err)),
+        None => {
+            print!("{source}");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,138 @@
+#![allow(clippy::cast_lossless)]
+
+mod analyze;
+mod assemble;
+mod decompile;
+mod run;
+
+use chip8_core::{AssembleError, ExecuteError, LoadError, MovieError};
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: chip8-cli <COMMAND> [OPTIONS]
+
+Commands:
+  run <ROM>              Run a ROM without a window and print the final screen
+  assemble <SOURCE>      Assemble Octo source into a ROM
+  decompile <ROM>        Print a ROM as Octo source that assembles back to it
+  analyze <ROM>          List the reachable instructions that depend on quirks
+
+Run options:
+  --platform <NAME>        Quirk profile: vip, chip48, schip10, schip11, schip, xochip [default: vip]
+  --frames <N>             Run N frames [default: 60]
+  --cycles <N>             Run N instructions instead of frames
+  --ipf <N>                Instructions per frame [default: 10]
+  --seed <N>               Seed for the random number generator
+  --ignore-undefined       Skip 0nnn machine code calls and undefined opcodes
+  --key <K>:<AT>[:<LEN>]   Hold key K (0-F) from frame AT for LEN frames [default LEN: 1],
+                           counted in instructions when --cycles is used
+  --output <FILE>          Write the final screen to a PBM file instead of printing it
+  --wav <FILE>             Record the buzzer to a 16-bit PCM WAV file
+  --record <FILE>          Record the keypad of every frame to a movie file
+  --movie <FILE>           Replay a movie and check that the final screen matches,
+                           in place of --platform, --seed, --ipf, --ignore-undefined,
+                           --key and --frames
+  --trace <FILE>           Log every executed instruction and the registers to FILE
+  --trace-format <FMT>     Layout of the trace lines, with the placeholders {cycle}, {pc},
+                           {op}, {v0}-{vf}, {i}, {sp}, {dt}, {st}, {mnemonic} and {octo},
+                           uppercase names printing uppercase hex
+  --crash-report <FILE>    Write a crash report to FILE if emulation fails
+
+Assemble options:
+  --output <FILE>          Write the ROM to FILE [default: SOURCE with a .ch8 extension]
+  --symbols <FILE>         Write the address of every label to FILE
+
+Decompile options:
+  --output <FILE>          Write the source to FILE instead of printing it
+
+Analyze options:
+  --dot <FILE>             Write the control-flow graph to FILE in Graphviz DOT format";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result =
+        match args.first().map(String::as_str) {
+            Some("run") => run::Options::parse(&args[1..]).and_then(|options| run::run(&options)),
+            Some("assemble") => assemble::Options::parse(&args[1..])
+                .and_then(|options| assemble::assemble(&options)),
+            Some("decompile") => decompile::Options::parse(&args[1..])
+                .and_then(|options| decompile::decompile(&options)),
+            Some("analyze") => {
+                analyze::Options::parse(&args[1..]).and_then(|options| analyze::analyze(&options))
+            }
+            Some("-h" | "--help") => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            Some(command) => Err(Error::Usage(format!("Unknown command {command:?}"))),
+            None => Err(Error::Usage(String::from("Missing command"))),
+        };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err @ Error::Usage(_)) => {
+            eprintln!("{err}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    Usage(String),
+    Io(PathBuf, io::Error),
+    Load(PathBuf, LoadError),
+    Movie(PathBuf, MovieError),
+    Assemble(PathBuf, AssembleError),
+    Execute(ExecuteError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "{message}"),
+            Self::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Load(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Movie(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Assemble(path, err) => write!(f, "{}:{err}", path.display()),
+            Self::Execute(err) => write!(f, "Emulation failed: {err:#}"),
+        }
+    }
+}
+
+/// Iterates over `--name value` pairs, leaving positional arguments to the caller.
+struct Args<'a> {
+    args: std::slice::Iter<'a, String>,
+}
+
+impl<'a> Args<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self { args: args.iter() }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.args.next().map(String::as_str)
+    }
+
+    fn value(&mut self, name: &str) -> Result<&'a str, Error> {
+        self.next()
+            .ok_or_else(|| Error::Usage(format!("Missing value for {name}")))
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self, name: &str) -> Result<T, Error>
+    where
+        T::Err: std::fmt::Display,
+    {
+        let value = self.value(name)?;
+        value
+            .parse()
+            .map_err(|err| Error::Usage(format!("Invalid value {value:?} for {name}: {err}")))
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{Args, Error};
+use chip8_core::{
+    AudioRenderer, Chip8, ExecuteError, Movie, Platform, TraceFormat, TraceWriter, UndefinedPolicy,
+    WavRecorder,
+};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+const KEY_COUNT: usize = 16;
+const WAV_SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Debug)]
+pub(crate) struct Options {
+    rom: PathBuf,
+    platform: Platform,
+    limit: Limit,
+    instructions_per_frame: u32,
+    seed: Option<u64>,
+    ignore_undefined: bool,
+    keys: Vec<KeyPress>,
+    output: Option<PathBuf>,
+    wav: Option<PathBuf>,
+    movie: Option<PathBuf>,
+    record: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    trace_format: Option<TraceFormat>,
+    crash_report: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    Frames(u64),
+    Cycles(u64),
+}
+
+/// A key held down from `at` for `len` frames, or instructions when running by cycles.
+#[derive(Debug, Clone, Copy)]
+struct KeyPress {
+    key: usize,
+    at: u64,
+    // The first time the key is released again
+    end: u64,
+}
+
+impl Options {
+    pub(crate) fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut args = Args::new(args);
+        let mut rom = None;
+        let mut options = Self {
+            rom: PathBuf::new(),
+            platform: Platform::CosmacVip,
+            limit: Limit::Frames(60),
+            instructions_per_frame: 10,
+            seed: None,
+            ignore_undefined: false,
+            keys: Vec::new(),
+            output: None,
+            wav: None,
+            movie: None,
+            record: None,
+            trace: None,
+            trace_format: None,
+            crash_report: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg {
+                "--platform" => options.platform = args.parse(arg)?,
+                "--frames" => options.limit = Limit::Frames(args.parse(arg)?),
+                "--cycles" => options.limit = Limit::Cycles(args.parse(arg)?),
+                "--ipf" => options.instructions_per_frame = args.parse(arg)?,
+                "--seed" => options.seed = Some(args.parse(arg)?),
+                "--ignore-undefined" => options.ignore_undefined = true,
+                "--key" => options.keys.push(parse_key_press(args.value(arg)?)?),
+                "--output" => options.output = Some(PathBuf::from(args.value(arg)?)),
+                "--wav" => options.wav = Some(PathBuf::from(args.value(arg)?)),
+                "--movie" => options.movie = Some(PathBuf::from(args.value(arg)?)),
+                "--record" => options.record = Some(PathBuf::from(args.value(arg)?)),
+                "--trace" => options.trace = Some(PathBuf::from(args.value(arg)?)),
+                "--trace-format" => options.trace_format = Some(args.parse(arg)?),
+                "--crash-report" => options.crash_report = Some(PathBuf::from(args.value(arg)?)),
+                _ if arg.starts_with("--") => {
+                    return Err(Error::Usage(format!("Unknown option {arg:?}")));
+                }
+                _ if rom.is_none() => rom = Some(PathBuf::from(arg)),
+                _ => return Err(Error::Usage(format!("Unexpected argument {arg:?}"))),
+            }
+        }
+
+        options.rom = rom.ok_or_else(|| Error::Usage(String::from("Missing ROM path")))?;
+        if options.instructions_per_frame == 0 {
+            return Err(Error::Usage(String::from("--ipf must be at least 1")));
+        }
+        if (options.movie.is_some() || options.record.is_some())
+            && matches!(options.limit, Limit::Cycles(_))
+        {
+            return Err(Error::Usage(String::from(
+                "Movies run by frames and can't be combined with --cycles",
+            )));
+        }
+        if options.movie.is_some() && options.record.is_some() {
+            return Err(Error::Usage(String::from(
+                "--movie and --record can't be combined",
+            )));
+        }
+
+        Ok(options)
+    }
+
+    fn apply_keys(&self, chip8: &mut Chip8, time: u64) {
+        for key in 0..KEY_COUNT {
+            let pressed = self
+                .keys
+                .iter()
+                .any(|press| press.key == key && (press.at..press.end).contains(&time));
+            chip8.set_key(key, pressed);
+        }
+    }
+}
+
+fn parse_key_press(value: &str) -> Result<KeyPress, Error> {
+    let invalid = || {
+        Error::Usage(format!(
+            "Invalid key press {value:?}, expected <K>:<AT>[:<LEN>]"
+        ))
+    };
+
+    let mut parts = value.split(':');
+    let key = parts
+        .next()
+        .and_then(|key| usize::from_str_radix(key, 16).ok())
+        .filter(|&key| key < KEY_COUNT)
+        .ok_or_else(invalid)?;
+    let at: u64 = parts
+        .next()
+        .and_then(|at| at.parse().ok())
+        .ok_or_else(invalid)?;
+    let len: u64 = match parts.next() {
+        Some(len) => len.parse().map_err(|_| invalid())?,
+        None => 1,
+    };
+
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    let end = at.checked_add(len).ok_or_else(invalid)?;
+
+    Ok(KeyPress { key, at, end })
+}
+
+pub(crate) fn run(options: &Options) -> Result<(), Error> {
+    let rom = fs::read(&options.rom).map_err(|err| Error::Io(options.rom.clone(), err))?;
+
+    let mut chip8 = Chip8::with_quirks(options.platform.quirks());
+    if let Some(seed) = options.seed {
+        chip8.set_seed(seed);
+    }
+    if options.ignore_undefined {
+        chip8.set_undefined_policy(UndefinedPolicy::Ignore);
+    }
+    chip8
+        .load(&rom)
+        .map_err(|err| Error::Load(options.rom.clone(), err))?;
+
+    let movie = match &options.movie {
+        Some(path) => {
+            let data = fs::read(path).map_err(|err| Error::Io(path.clone(), err))?;
+            let movie = Movie::from_bytes(&data).map_err(|err| Error::Movie(path.clone(), err))?;
+            movie
+                .start(&mut chip8, &rom)
+                .map_err(|err| Error::Movie(path.clone(), err))?;
+            Some(movie)
+        }
+        None => None,
+    };
+    let mut recording = match &options.record {
+        Some(_) => {
+            let seed = chip8.seed().unwrap_or_default();
+            let movie = Movie::record(&mut chip8, &rom, seed, options.instructions_per_frame)
+                .map_err(|err| Error::Load(options.rom.clone(), err))?;
+            Some(movie)
+        }
+        None => None,
+    };
+
+    // Shared with the machine so the log can be flushed and checked once the run is over
+    let tracer = match &options.trace {
+        Some(path) => {
+            let file = File::create(path).map_err(|err| Error::Io(path.clone(), err))?;
+            let mut writer = TraceWriter::new(BufWriter::new(file));
+            if let Some(format) = &options.trace_format {
+                writer = writer.with_format(format.clone());
+            }
+            let tracer = Arc::new(Mutex::new(writer));
+            chip8.set_tracer(Arc::clone(&tracer));
+            Some(tracer)
+        }
+        None => None,
+    };
+
+    let mut recorder = options
+        .wav
+        .as_ref()
+        .map(|_| WavRecorder::new(AudioRenderer::new(WAV_SAMPLE_RATE)));
+    let mut record_frame = |chip8: &Chip8| {
+        if let Some(recorder) = &mut recorder {
+            recorder.record_frame(chip8);
+        }
+        if let Some(recording) = &mut recording {
+            recording.record_frame(chip8);
+        }
+    };
+
+    let crashed = |err: ExecuteError| match &options.crash_report {
+        Some(path) => match File::create(path).and_then(|file| err.write_report(file)) {
+            Ok(()) => Error::Execute(err),
+            Err(io_err) => Error::Io(path.clone(), io_err),
+        },
+        None => Error::Execute(err),
+    };
+
+    match options.limit {
+        Limit::Frames(frames) => {
+            let (frames, instructions_per_frame) = movie
+                .as_ref()
+                .map_or((frames, options.instructions_per_frame), |movie| {
+                    (movie.len() as u64, movie.instructions_per_frame())
+                });
+            for frame in 0..frames {
+                match &movie {
+                    Some(movie) => {
+                        movie.apply_frame(&mut chip8, frame as usize);
+                    }
+                    None => options.apply_keys(&mut chip8, frame),
+                }
+                chip8.run_frame(instructions_per_frame).map_err(&crashed)?;
+                record_frame(&chip8);
+            }
+        }
+        Limit::Cycles(cycles) => {
+            // Only instructions that actually run are counted, a draw waiting for the display
+            // just ends the frame early like `run_frame` does
+            let mut executed = 0;
+            while executed < cycles && !chip8.is_halted() {
+                options.apply_keys(&mut chip8, executed);
+                let before = chip8.cycles();
+                chip8.emulate().map_err(&crashed)?;
+                executed += chip8.cycles() - before;
+                if chip8.frame_cycles() >= options.instructions_per_frame
+                    || chip8.is_display_waiting()
+                {
+                    chip8.tick_timers();
+                    record_frame(&chip8);
+                }
+            }
+        }
+    }
+
+    if let (Some(path), Some(tracer)) = (&options.trace, &tracer) {
+        tracer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .finish()
+            .map_err(|err| Error::Io(path.clone(), err))?;
+    }
+
+    if let (Some(path), Some(movie)) = (&options.movie, &movie) {
+        movie
+            .verify(&chip8)
+            .map_err(|err| Error::Movie(path.clone(), err))?;
+    }
+    if let (Some(path), Some(recording)) = (&options.record, &recording) {
+        fs::write(path, recording.to_bytes()).map_err(|err| Error::Io(path.clone(), err))?;
+    }
+
+    if let (Some(path), Some(recorder)) = (&options.wav, &recorder) {
+        File::create(path)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                recorder.write_to(&mut writer)?;
+                writer.flush()
+            })
+            .map_err(|err| Error::Io(path.clone(), err))?;
+    }
+
+    match &options.output {
+        Some(path) => fs::write(path, to_pbm(&chip8)).map_err(|err| Error::Io(path.clone(), err)),
+        None => {
+            print!("{}", to_ascii(&chip8));
+            Ok(())
+        }
+    }
+}
+
+/// Renders the screen with `.` for unlit pixels, `#` for pixels on the first plane and the
+/// hexadecimal plane mask for any other XO-CHIP plane combination.
+fn to_ascii(chip8: &Chip8) -> String {
+    let mut ascii = String::with_capacity((chip8.width() + 1) * chip8.height());
+
+    for row in chip8.framebuffer().chunks_exact(chip8.width()) {
+        ascii.extend(row.iter().map(|&pixel| match pixel {
+            0 => '.',
+            1 => '#',
+            planes => char::from_digit(planes as u32, 16).unwrap_or('?'),
+        }));
+        ascii.push('\n');
+    }
+
+    ascii
+}
+
+/// Encodes the screen as a binary PBM image, where any lit plane is black.
+fn to_pbm(chip8: &Chip8) -> Vec<u8> {
+    let mut pbm = format!("P4\n{} {}\n", chip8.width(), chip8.height()).into_bytes();
+
+    for row in chip8.framebuffer().chunks_exact(chip8.width()) {
+        pbm.extend(row.chunks(8).map(|pixels| {
+            pixels.iter().enumerate().fold(0u8, |byte, (i, &pixel)| {
+                byte | (u8::from(pixel != 0) << (7 - i))
+            })
+        }));
+    }
+
+    pbm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_press() {
+        let press = parse_key_press("a:10:5").unwrap();
+        assert_eq!((press.key, press.at, press.end), (0xA, 10, 15));
+        assert_eq!(parse_key_press("1:3").unwrap().end, 4);
+
+        assert!(parse_key_press("1:18446744073709551615:1").is_err());
+        assert!(parse_key_press("10:0").is_err());
+        assert!(parse_key_press("1:0:1:2").is_err());
+    }
+}
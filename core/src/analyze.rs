@@ -0,0 +1,509 @@
+use crate::flow::{self, EdgeKind, Step};
+use crate::{
+    HIRES_HEIGHT, HIRES_WIDTH, Instruction, LORES_HEIGHT, LORES_WIDTH, Line, LineKind, Platform,
+    START_ADDR, Syntax,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, Write};
+
+/// A quirk that changes how an instruction behaves, named after the [`crate::Quirks`] flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Quirk {
+    /// `8xy1`, `8xy2` and `8xy3` may reset vF.
+    VfReset,
+    /// `Fx55` and `Fx65` may increment the index register.
+    Memory,
+    /// A sprite crosses the edge of the screen, where it may be clipped or wrap around.
+    Clipping,
+    /// `8xy6` and `8xyE` may shift vX instead of vY.
+    Shifting,
+    /// `Bnnn` may add vX instead of v0.
+    Jumping,
+}
+
+impl Quirk {
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::VfReset => "vf_reset",
+            Self::Memory => "memory",
+            Self::Clipping => "clipping",
+            Self::Shifting => "shifting",
+            Self::Jumping => "jumping",
+        }
+    }
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A reachable instruction whose behaviour depends on a quirk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuirkUse {
+    pub addr: u16,
+    pub instruction: Instruction,
+    pub quirk: Quirk,
+}
+
+/// A run of instructions that only the first one is jumped to and only the last one branches
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u16,
+    /// The instructions of the block, all [`LineKind::Code`].
+    pub lines: Vec<Line>,
+}
+
+/// An edge of the control-flow graph, between the starts of two blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: u16,
+    pub to: u16,
+    pub kind: EdgeKind,
+}
+
+/// The control-flow graph of a ROM and the quirks its reachable code depends on.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    blocks: Vec<BasicBlock>,
+    edges: Vec<Edge>,
+    quirk_uses: Vec<QuirkUse>,
+    platform: Platform,
+}
+
+impl Analysis {
+    /// Returns the basic blocks, by address.
+    #[must_use]
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    #[must_use]
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Returns the reachable instructions whose behaviour depends on a quirk, by address.
+    #[must_use]
+    pub fn quirk_uses(&self) -> &[QuirkUse] {
+        &self.quirk_uses
+    }
+
+    /// Returns the quirks the ROM depends on.
+    #[must_use]
+    pub fn quirks(&self) -> BTreeSet<Quirk> {
+        self.quirk_uses
+            .iter()
+            .map(|quirk_use| quirk_use.quirk)
+            .collect()
+    }
+
+    /// Returns the platform the ROM was most likely written for: XO-CHIP when it uses XO-CHIP
+    /// instructions, SCHIP 1.1 when it uses SUPER-CHIP ones, the COSMAC VIP otherwise.
+    ///
+    /// Only the opcode families are considered. Which way the [`quirk_uses`](Self::quirk_uses)
+    /// should go can't be told from the code, so their flags may need adjusting.
+    #[must_use]
+    pub const fn suggested_platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Writes the graph in the Graphviz DOT language, with the blocks that depend on a quirk
+    /// outlined in red.
+    pub fn write_dot(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "digraph rom {{")?;
+        writeln!(writer, "  node [shape=box, fontname=\"monospace\"];")?;
+
+        for block in &self.blocks {
+            let mut label = String::new();
+            let mut depends = false;
+            for line in &block.lines {
+                label.push_str(&format!("{}", line.listing(Syntax::Octo)));
+                for quirk_use in self.quirk_uses.iter().filter(|q| q.addr == line.addr) {
+                    label.push_str(&format!("  [{}]", quirk_use.quirk));
+                    depends = true;
+                }
+                label.push_str("\\l");
+            }
+            let color = if depends { ", color=red" } else { "" };
+            writeln!(
+                writer,
+                "  \"0x{:03X}\" [label=\"{label}\"{color}];",
+                block.start
+            )?;
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Next => "",
+                EdgeKind::Skip => " [label=\"skip\", style=dashed]",
+                EdgeKind::Jump => " [label=\"jump\"]",
+                EdgeKind::Call => " [label=\"call\", style=bold]",
+                EdgeKind::Computed => " [label=\"jump0\", style=dotted]",
+            };
+            writeln!(
+                writer,
+                "  \"0x{:03X}\" -> \"0x{:03X}\"{style};",
+                edge.from, edge.to
+            )?;
+        }
+
+        writeln!(writer, "}}")
+    }
+}
+
+/// Builds the control-flow graph of a ROM loaded at address `0x200` by following jumps, calls,
+/// skips and `Bnnn` jump tables, and finds the reachable instructions that behave differently
+/// across quirks.
+///
+/// Sprites are checked against the edges of the screen by tracking the registers that hold
+/// constants along the way, so draws at computed coordinates are only reported when the
+/// values can be worked out.
+#[must_use]
+pub fn analyze(rom: &[u8]) -> Analysis {
+    let steps = flow::explore(rom);
+    let (blocks, edges) = graph(rom, &steps);
+    let states = propagate(rom, &steps);
+
+    let mut quirk_uses = Vec::new();
+    for (&addr, step) in &steps {
+        let quirk = match step.instruction {
+            Instruction::Or { .. } | Instruction::And { .. } | Instruction::Xor { .. } => {
+                Some(Quirk::VfReset)
+            }
+            Instruction::Store { .. } | Instruction::Restore { .. } => Some(Quirk::Memory),
+            Instruction::ShiftRight { .. } | Instruction::ShiftLeft { .. } => Some(Quirk::Shifting),
+            Instruction::JumpOffset { .. } => Some(Quirk::Jumping),
+            Instruction::Draw { x, y, n } => states
+                .get(&addr)
+                .filter(|state| state.crosses_edge(x, y, n))
+                .map(|_| Quirk::Clipping),
+            _ => None,
+        };
+        if let Some(quirk) = quirk {
+            quirk_uses.push(QuirkUse {
+                addr,
+                instruction: step.instruction,
+                quirk,
+            });
+        }
+    }
+
+    Analysis {
+        blocks,
+        edges,
+        quirk_uses,
+        platform: suggest_platform(&steps),
+    }
+}
+
+/// Groups the instructions into basic blocks and links them.
+fn graph(rom: &[u8], steps: &BTreeMap<u16, Step>) -> (Vec<BasicBlock>, Vec<Edge>) {
+    let successors: BTreeMap<u16, Vec<(u16, EdgeKind)>> = steps
+        .iter()
+        .map(|(&addr, step)| (addr, step.successors(rom, addr)))
+        .collect();
+    let falls_through = |addr: u16| {
+        let next = addr.wrapping_add(steps[&addr].size());
+        successors[&addr] == [(next, EdgeKind::Next)]
+    };
+
+    // Blocks start at the entry point and wherever a branch leads
+    let mut leaders = BTreeSet::from([START_ADDR as u16]);
+    for (&addr, targets) in &successors {
+        if !falls_through(addr) {
+            leaders.extend(targets.iter().map(|&(to, _)| to));
+        }
+    }
+
+    let mut blocks: Vec<BasicBlock> = Vec::new();
+    let mut previous: Option<u16> = None;
+    for (&addr, step) in steps {
+        let continues = previous.is_some_and(|previous| {
+            falls_through(previous)
+                && previous.wrapping_add(steps[&previous].size()) == addr
+                && !leaders.contains(&addr)
+        });
+        let line = Line {
+            addr,
+            kind: LineKind::Code {
+                opcode: step.opcode,
+                instruction: step.instruction,
+                long_addr: step.long_addr,
+            },
+        };
+        match blocks.last_mut() {
+            Some(block) if continues => block.lines.push(line),
+            _ => blocks.push(BasicBlock {
+                start: addr,
+                lines: vec![line],
+            }),
+        }
+        previous = Some(addr);
+    }
+
+    let mut edges = Vec::new();
+    for block in &blocks {
+        let last = block.lines.last().map_or(block.start, |line| line.addr);
+        for &(to, kind) in &successors[&last] {
+            if steps.contains_key(&to) {
+                edges.push(Edge {
+                    from: block.start,
+                    to,
+                    kind,
+                });
+            }
+        }
+    }
+
+    (blocks, edges)
+}
+
+/// What is known about the machine before an instruction runs, on every path to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Known {
+    v: [Option<u8>; 16],
+    hires: Option<bool>,
+}
+
+impl Known {
+    const UNKNOWN: Self = Self {
+        v: [None; 16],
+        hires: None,
+    };
+
+    fn meet(self, other: Self) -> Self {
+        let agree = |a: Option<u8>, b: Option<u8>| a.filter(|_| a == b);
+        Self {
+            v: std::array::from_fn(|i| agree(self.v[i], other.v[i])),
+            hires: self.hires.filter(|_| self.hires == other.hires),
+        }
+    }
+
+    /// Returns the state after running the instruction.
+    fn after(mut self, instruction: Instruction) -> Self {
+        let v = &mut self.v;
+        let both = |x: u8, y: u8| v[x as usize].zip(v[y as usize]);
+
+        match instruction {
+            Instruction::LoadByte { x, byte } => v[x as usize] = Some(byte),
+            Instruction::AddByte { x, byte } => {
+                v[x as usize] = v[x as usize].map(|a| a.wrapping_add(byte));
+            }
+            Instruction::Move { x, y } => v[x as usize] = v[y as usize],
+            Instruction::Add { x, y } | Instruction::Sub { x, y } | Instruction::SubN { x, y } => {
+                let result = both(x, y).map(|(a, b)| match instruction {
+                    Instruction::Add { .. } => a.overflowing_add(b),
+                    Instruction::Sub { .. } => a.overflowing_sub(b),
+                    _ => b.overflowing_sub(a),
+                });
+                let no_borrow = !matches!(instruction, Instruction::Add { .. });
+                v[x as usize] = result.map(|(value, _)| value);
+                v[0xF] = result.map(|(_, carry)| u8::from(carry != no_borrow));
+            }
+            Instruction::Or { x, y } | Instruction::And { x, y } | Instruction::Xor { x, y } => {
+                v[x as usize] = both(x, y).map(|(a, b)| match instruction {
+                    Instruction::Or { .. } => a | b,
+                    Instruction::And { .. } => a & b,
+                    _ => a ^ b,
+                });
+                // Depends on the vF reset quirk
+                v[0xF] = None;
+            }
+            Instruction::ShiftRight { x, .. } | Instruction::ShiftLeft { x, .. } => {
+                // Depends on the shifting quirk
+                v[x as usize] = None;
+                v[0xF] = None;
+            }
+            Instruction::Random { x, .. }
+            | Instruction::LoadDelay { x }
+            | Instruction::WaitKey { x } => v[x as usize] = None,
+            Instruction::Restore { x } | Instruction::LoadFlags { x } => {
+                v[..=x as usize].fill(None);
+            }
+            Instruction::LoadRange { x, y } => v[x.min(y) as usize..=x.max(y) as usize].fill(None),
+            Instruction::Draw { .. } => v[0xF] = None,
+            Instruction::Hires => self.hires = Some(true),
+            Instruction::Lores => self.hires = Some(false),
+            _ => {}
+        }
+        self
+    }
+
+    /// Returns whether a sprite drawn at vX, vY crosses the right or bottom edge of the screen
+    /// in any resolution the machine may be in.
+    fn crosses_edge(&self, x: u8, y: u8, n: u8) -> bool {
+        let (Some(x), Some(y)) = (self.v[x as usize], self.v[y as usize]) else {
+            return false;
+        };
+        let (width, height) = if n == 0 { (16, 16) } else { (8, n as usize) };
+        let screens = match self.hires {
+            Some(true) => &[(HIRES_WIDTH, HIRES_HEIGHT)][..],
+            Some(false) => &[(LORES_WIDTH, LORES_HEIGHT)][..],
+            None => &[(LORES_WIDTH, LORES_HEIGHT), (HIRES_WIDTH, HIRES_HEIGHT)][..],
+        };
+        screens.iter().any(|&(screen_width, screen_height)| {
+            x as usize % screen_width + width > screen_width
+                || y as usize % screen_height + height > screen_height
+        })
+    }
+}
+
+/// Works out the registers holding the same constant on every path to each instruction.
+fn propagate(rom: &[u8], steps: &BTreeMap<u16, Step>) -> BTreeMap<u16, Known> {
+    let entry = START_ADDR as u16;
+    let mut states = BTreeMap::new();
+    if steps.contains_key(&entry) {
+        states.insert(
+            entry,
+            Known {
+                hires: Some(false),
+                ..Known::UNKNOWN
+            },
+        );
+    }
+    let mut pending = vec![entry];
+
+    while let Some(addr) = pending.pop() {
+        let (Some(step), Some(&state)) = (steps.get(&addr), states.get(&addr)) else {
+            continue;
+        };
+        let after = state.after(step.instruction);
+
+        for (to, kind) in step.successors(rom, addr) {
+            if !steps.contains_key(&to) {
+                continue;
+            }
+            // A subroutine may change anything before returning
+            let state = match (step.instruction, kind) {
+                (Instruction::Call { .. }, EdgeKind::Next) => Known::UNKNOWN,
+                _ => after,
+            };
+            let merged = states.get(&to).map_or(state, |&old| old.meet(state));
+            if states.insert(to, merged) != Some(merged) {
+                pending.push(to);
+            }
+        }
+    }
+
+    states
+}
+
+fn suggest_platform(steps: &BTreeMap<u16, Step>) -> Platform {
+    let uses = |f: fn(Instruction) -> bool| steps.values().any(|step| f(step.instruction));
+
+    if uses(|instruction| {
+        matches!(
+            instruction,
+            Instruction::ScrollUp { .. }
+                | Instruction::SaveRange { .. }
+                | Instruction::LoadRange { .. }
+                | Instruction::LoadIndexLong
+                | Instruction::Plane { .. }
+                | Instruction::Audio
+                | Instruction::SetPitch { .. }
+        )
+    }) {
+        Platform::XoChip
+    } else if uses(|instruction| {
+        matches!(
+            instruction,
+            Instruction::ScrollDown { .. }
+                | Instruction::ScrollRight
+                | Instruction::ScrollLeft
+                | Instruction::Exit
+                | Instruction::Lores
+                | Instruction::Hires
+                | Instruction::BigFont { .. }
+                | Instruction::SaveFlags { .. }
+                | Instruction::LoadFlags { .. }
+        )
+    }) {
+        Platform::SuperChip11
+    } else {
+        Platform::CosmacVip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble;
+
+    const SOURCE: &str = "
+        : main
+          v0 := 60
+          v1 := 0
+          i := main
+          sprite v0 v1 4
+          v2 >>= v3
+          save v1
+          v1 |= v2
+          jump0 table
+        : table
+          jump main
+          jump main
+    ";
+
+    #[test]
+    fn quirk_uses() {
+        let rom = assemble(SOURCE).unwrap().rom;
+        let analysis = analyze(&rom);
+
+        let uses: Vec<(u16, Quirk)> = analysis
+            .quirk_uses()
+            .iter()
+            .map(|quirk_use| (quirk_use.addr, quirk_use.quirk))
+            .collect();
+        assert_eq!(
+            uses,
+            [
+                (0x206, Quirk::Clipping),
+                (0x208, Quirk::Shifting),
+                (0x20A, Quirk::Memory),
+                (0x20C, Quirk::VfReset),
+                (0x20E, Quirk::Jumping),
+            ]
+        );
+        assert_eq!(analysis.suggested_platform(), Platform::CosmacVip);
+
+        // The sprite fits once v0 is moved away from the edge
+        let rom = assemble(&SOURCE.replace("v0 := 60", "v0 := 56"))
+            .unwrap()
+            .rom;
+        assert!(!analyze(&rom).quirks().contains(&Quirk::Clipping));
+
+        let rom = assemble(&SOURCE.replace(": main", ": main hires"))
+            .unwrap()
+            .rom;
+        assert_eq!(analyze(&rom).suggested_platform(), Platform::SuperChip11);
+    }
+
+    #[test]
+    fn dot() {
+        let rom = assemble(SOURCE).unwrap().rom;
+        let analysis = analyze(&rom);
+        assert_eq!(
+            analysis
+                .blocks()
+                .iter()
+                .map(|block| block.start)
+                .collect::<Vec<_>>(),
+            [0x200, 0x210, 0x212]
+        );
+
+        let mut dot = Vec::new();
+        analysis.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("digraph rom {\n"));
+        assert!(dot.contains("  [jumping]\\l\", color=red];"));
+        assert!(dot.contains("\"0x200\" -> \"0x210\" [label=\"jump0\", style=dotted];"));
+        assert!(dot.contains("\"0x200\" -> \"0x212\" [label=\"jump0\", style=dotted];"));
+        assert!(dot.contains("\"0x210\" -> \"0x200\" [label=\"jump\"];"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
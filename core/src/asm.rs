@@ -0,0 +1,1086 @@
+mod calc;
+mod token;
+
+use crate::{Instruction, START_ADDR};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use token::{Token, Tokens};
+
+// Bounds expansions so that a macro invoking itself fails instead of running forever
+const MAX_EXPANSIONS: usize = 10_000;
+
+/// Words that can't name a label, a constant, an alias or a macro.
+const RESERVED: &[&str] = &[
+    ":=",
+    "|=",
+    "&=",
+    "^=",
+    "-=",
+    "=-",
+    "+=",
+    ">>=",
+    "<<=",
+    "==",
+    "!=",
+    "<",
+    ">",
+    "<=",
+    ">=",
+    "key",
+    "-key",
+    "hex",
+    "bighex",
+    "random",
+    "delay",
+    ":",
+    ":next",
+    ":unpack",
+    ":breakpoint",
+    ":monitor",
+    ":proto",
+    ":alias",
+    ":const",
+    ":macro",
+    ":calc",
+    ":byte",
+    ":call",
+    ":org",
+    ":assert",
+    ":pointer",
+    ";",
+    "return",
+    "clear",
+    "bcd",
+    "save",
+    "load",
+    "buzzer",
+    "if",
+    "then",
+    "begin",
+    "else",
+    "end",
+    "jump",
+    "jump0",
+    "native",
+    "sprite",
+    "loop",
+    "while",
+    "again",
+    "scroll-down",
+    "scroll-up",
+    "scroll-right",
+    "scroll-left",
+    "lores",
+    "hires",
+    "exit",
+    "saveflags",
+    "loadflags",
+    "i",
+    "audio",
+    "plane",
+    "pitch",
+    "long",
+    "-",
+    "{",
+    "}",
+];
+
+/// A ROM assembled from Octo source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    /// The bytes to load at address `0x200`.
+    pub rom: Vec<u8>,
+    /// The address of every label.
+    pub symbols: BTreeMap<String, u16>,
+}
+
+/// An error in the source, at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Assembles Octo source into a ROM loaded at address `0x200`.
+///
+/// Every statement of Octo is supported, including the SUPER-CHIP and XO-CHIP ones, along with
+/// labels, `:next`, `:const`, `:alias`, `:macro`, `:calc`, `:org`, `:byte`, `:pointer`,
+/// `:call`, `:unpack` and `:assert`. Conditions can guard one statement with `if ... then` or
+/// a block with `if ... begin ... else ... end`, and loops are written `loop ... again` with
+/// any number of `while` conditions. Other numbers are emitted as data bytes, so sprites are
+/// written as lists of bytes such as `0b11110000 0x90`. Strings are not supported.
+///
+/// Like Octo, when the program has a `main` label that isn't at `0x200`, a `jump main` is
+/// placed there.
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    let tokens = token::tokenize(source);
+    let program = Assembler::new(tokens.clone(), false).run()?;
+
+    match program.symbols.get("main") {
+        Some(&main) if main != START_ADDR as u16 => Assembler::new(tokens, true).run(),
+        _ => Ok(program),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Macro<'a> {
+    params: Vec<&'a str>,
+    body: Vec<Token<'a>>,
+}
+
+/// An address operand, which may name a label defined further down.
+#[derive(Debug, Clone, Copy)]
+enum Target<'a> {
+    Known(u16),
+    Forward(Token<'a>),
+}
+
+/// An address written once the label it names is defined.
+#[derive(Debug, Clone, Copy)]
+struct Fixup<'a> {
+    addr: usize,
+    kind: FixupKind,
+    label: Token<'a>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FixupKind {
+    /// The `nnn` field of the instruction at the address.
+    Addr,
+    /// The 16-bit word at the address.
+    Long,
+    /// The bytes of the `v0 := ... v1 := ...` pair at the address emitted by `:unpack`.
+    Unpack { long: bool },
+}
+
+/// An open `if ... begin` or `loop` block.
+#[derive(Debug, Clone)]
+enum Flow<'a> {
+    If {
+        token: Token<'a>,
+        // The jump taken when the condition is false, or the one skipping the `else` branch
+        jump: usize,
+        has_else: bool,
+    },
+    Loop {
+        token: Token<'a>,
+        start: usize,
+        // The jumps emitted by `while`, which leave the loop
+        breaks: Vec<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(u8),
+    Byte(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    Compare {
+        x: u8,
+        comparison: Comparison,
+        operand: Operand,
+    },
+    Key {
+        x: u8,
+        pressed: bool,
+    },
+}
+
+struct Assembler<'a> {
+    tokens: Tokens<'a>,
+    // The statement being assembled, where errors found while emitting it are reported
+    statement: Token<'a>,
+    rom: Vec<u8>,
+    // Which bytes of `rom` were emitted, to catch `:org` overwriting code
+    written: Vec<bool>,
+    here: usize,
+    labels: HashMap<&'a str, u16>,
+    constants: HashMap<&'a str, f64>,
+    aliases: HashMap<&'a str, u8>,
+    macros: HashMap<&'a str, Macro<'a>>,
+    expansions: usize,
+    fixups: Vec<Fixup<'a>>,
+    flow: Vec<Flow<'a>>,
+    // Whether to start with a jump to `main`
+    entry_jump: bool,
+}
+
+impl<'a> Assembler<'a> {
+    fn new(tokens: Vec<Token<'a>>, entry_jump: bool) -> Self {
+        let statement = tokens.first().copied().unwrap_or(Token {
+            text: "",
+            line: 1,
+            column: 1,
+        });
+        let reserved = if entry_jump { 2 } else { 0 };
+
+        Self {
+            tokens: Tokens::new(tokens),
+            statement,
+            rom: vec![0; reserved],
+            written: vec![true; reserved],
+            here: START_ADDR + reserved,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            expansions: 0,
+            fixups: Vec::new(),
+            flow: Vec::new(),
+            entry_jump,
+        }
+    }
+
+    fn run(mut self) -> Result<Program, AssembleError> {
+        while let Some(token) = self.tokens.next() {
+            self.statement = token;
+            self.statement(token)?;
+        }
+
+        if let Some(flow) = self.flow.last() {
+            return Err(match flow {
+                Flow::If { token, .. } => token.error("`begin` is never closed by `end`"),
+                Flow::Loop { token, .. } => token.error("`loop` is never closed by `again`"),
+            });
+        }
+
+        for fixup in std::mem::take(&mut self.fixups) {
+            let label = fixup.label;
+            let addr = *self
+                .labels
+                .get(label.text)
+                .ok_or_else(|| label.error(format!("Undefined name `{}`", label.text)))?;
+            self.statement = label;
+            self.patch(fixup.addr, fixup.kind, addr as usize)?;
+        }
+
+        if self.entry_jump {
+            let main = self.labels["main"] as usize;
+            self.patch(START_ADDR, FixupKind::Addr, main)?;
+            self.rom[0] |= 0x10;
+        }
+
+        Ok(Program {
+            rom: self.rom,
+            symbols: self
+                .labels
+                .into_iter()
+                .map(|(name, addr)| (name.to_owned(), addr))
+                .collect(),
+        })
+    }
+
+    fn statement(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        match token.text {
+            ":" => {
+                let name = self.name()?;
+                self.define_label(name, self.here)
+            }
+            ":next" => {
+                // Names the operand byte of the next instruction, for self-modifying code
+                let name = self.name()?;
+                self.define_label(name, self.here + 1)
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.tokens.expect()?;
+                let value = self.known_value(value)?;
+                self.constants.insert(name.text, value);
+                Ok(())
+            }
+            ":calc" => {
+                let name = self.name()?;
+                self.tokens.expect_text("{")?;
+                let value = self.calc()?;
+                self.constants.insert(name.text, value);
+                Ok(())
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let x = self.register()?;
+                self.aliases.insert(name.text, x);
+                Ok(())
+            }
+            ":macro" => self.define_macro(),
+            ":org" => {
+                let value = self.tokens.expect()?;
+                let addr = self.integer(value, self.known_value(value)?, 0xFFFF, "16 bits")?;
+                if addr < START_ADDR as i64 {
+                    return Err(value.error(format!(
+                        "Address {addr:#05x} is below the start of the program"
+                    )));
+                }
+                self.here = addr as usize;
+                Ok(())
+            }
+            ":byte" => {
+                let byte = if self.tokens.next_if("{") {
+                    let value = self.calc()?;
+                    to_byte(token, value)?
+                } else {
+                    self.byte()?
+                };
+                self.emit(byte)
+            }
+            ":pointer" => {
+                let target = self.tokens.expect()?;
+                let addr = self.target(target, 0xFFFF, "16 bits")?;
+                let addr = self.resolve(addr, FixupKind::Long);
+                self.emit(addr.to_be_bytes()[0])?;
+                self.emit(addr.to_be_bytes()[1])
+            }
+            ":call" => self.jump(|addr| Instruction::Call { addr }),
+            ":unpack" => self.unpack(),
+            ":breakpoint" => {
+                // Breakpoints are set in the debugger instead
+                self.name()?;
+                Ok(())
+            }
+            ":monitor" => {
+                self.tokens.expect()?;
+                self.tokens.expect()?;
+                Ok(())
+            }
+            ":assert" => {
+                self.tokens.expect_text("{")?;
+                if self.calc()? == 0.0 {
+                    return Err(token.error("Assertion failed"));
+                }
+                Ok(())
+            }
+            ":proto" | ":stringmode" => {
+                Err(token.error(format!("`{}` is not supported", token.text)))
+            }
+            ";" | "return" => self.instruction(Instruction::Return),
+            "clear" => self.instruction(Instruction::ClearScreen),
+            "hires" => self.instruction(Instruction::Hires),
+            "lores" => self.instruction(Instruction::Lores),
+            "exit" => self.instruction(Instruction::Exit),
+            "scroll-left" => self.instruction(Instruction::ScrollLeft),
+            "scroll-right" => self.instruction(Instruction::ScrollRight),
+            "audio" => self.instruction(Instruction::Audio),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.instruction(Instruction::ScrollDown { n })
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.instruction(Instruction::ScrollUp { n })
+            }
+            "plane" => {
+                let n = self.nibble()?;
+                self.instruction(Instruction::Plane { n })
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.instruction(Instruction::Bcd { x })
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.instruction(Instruction::SaveFlags { x })
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.instruction(Instruction::LoadFlags { x })
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                let instruction = if self.tokens.next_if("-") {
+                    let y = self.register()?;
+                    if token.text == "save" {
+                        Instruction::SaveRange { x, y }
+                    } else {
+                        Instruction::LoadRange { x, y }
+                    }
+                } else if token.text == "save" {
+                    Instruction::Store { x }
+                } else {
+                    Instruction::Restore { x }
+                };
+                self.instruction(instruction)
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.instruction(Instruction::Draw { x, y, n })
+            }
+            "jump" => self.jump(|addr| Instruction::Jump { addr }),
+            "jump0" => self.jump(|addr| Instruction::JumpOffset { addr }),
+            "native" => self.jump(|addr| Instruction::Sys { addr }),
+            "delay" | "buzzer" | "pitch" => {
+                self.tokens.expect_text(":=")?;
+                let x = self.register()?;
+                self.instruction(match token.text {
+                    "delay" => Instruction::SetDelay { x },
+                    "buzzer" => Instruction::SetSound { x },
+                    _ => Instruction::SetPitch { x },
+                })
+            }
+            "i" => self.index(),
+            "if" => self.conditional(token),
+            "else" => self.else_branch(token),
+            "end" => self.end(token),
+            "loop" => {
+                self.flow.push(Flow::Loop {
+                    token,
+                    start: self.here,
+                    breaks: Vec::new(),
+                });
+                Ok(())
+            }
+            "while" => self.while_condition(token),
+            "again" => self.again(token),
+            text => {
+                if let Some(m) = self.macros.get(text).cloned() {
+                    self.expand(token, &m)
+                } else if let Some(x) = self.register_of(token) {
+                    self.register_statement(x)
+                } else if let Some(value) = self.value(token).filter(|_| !self.is_label(token)) {
+                    self.emit(to_byte(token, value)?)
+                } else {
+                    // Any other name calls a subroutine
+                    let target = self.target(token, 0xFFF, "12 bits")?;
+                    let addr = self.resolve(target, FixupKind::Addr);
+                    self.instruction(Instruction::Call { addr })
+                }
+            }
+        }
+    }
+
+    // vx := ..., vx += ... and the other register operators
+    fn register_statement(&mut self, x: u8) -> Result<(), AssembleError> {
+        let op = self.tokens.expect()?;
+        let operand = self.tokens.expect()?;
+        let y = self.register_of(operand);
+
+        let instruction = match (op.text, y) {
+            (":=", _) if operand.text == "random" => Instruction::Random {
+                x,
+                byte: self.byte()?,
+            },
+            (":=", _) if operand.text == "key" => Instruction::WaitKey { x },
+            (":=", _) if operand.text == "delay" => Instruction::LoadDelay { x },
+            (":=", Some(y)) => Instruction::Move { x, y },
+            (":=", None) => Instruction::LoadByte {
+                x,
+                byte: self.byte_of(operand)?,
+            },
+            ("+=", Some(y)) => Instruction::Add { x, y },
+            ("+=", None) => Instruction::AddByte {
+                x,
+                byte: self.byte_of(operand)?,
+            },
+            ("-=", Some(y)) => Instruction::Sub { x, y },
+            ("-=", None) => Instruction::AddByte {
+                x,
+                byte: self.byte_of(operand)?.wrapping_neg(),
+            },
+            ("=-", Some(y)) => Instruction::SubN { x, y },
+            ("|=", Some(y)) => Instruction::Or { x, y },
+            ("&=", Some(y)) => Instruction::And { x, y },
+            ("^=", Some(y)) => Instruction::Xor { x, y },
+            (">>=", Some(y)) => Instruction::ShiftRight { x, y },
+            ("<<=", Some(y)) => Instruction::ShiftLeft { x, y },
+            ("=-" | "|=" | "&=" | "^=" | ">>=" | "<<=", None) => {
+                return Err(operand.error(format!("Expected a register, found `{}`", operand.text)));
+            }
+            _ => return Err(op.error(format!("Unknown operator `{}`", op.text))),
+        };
+        self.instruction(instruction)
+    }
+
+    // i := ..., i += vx
+    fn index(&mut self) -> Result<(), AssembleError> {
+        let op = self.tokens.expect()?;
+        match op.text {
+            ":=" => {}
+            "+=" => {
+                let x = self.register()?;
+                return self.instruction(Instruction::AddIndex { x });
+            }
+            _ => return Err(op.error(format!("Unknown operator `{}`", op.text))),
+        }
+
+        let operand = self.tokens.expect()?;
+        match operand.text {
+            "hex" => {
+                let x = self.register()?;
+                self.instruction(Instruction::Font { x })
+            }
+            "bighex" => {
+                let x = self.register()?;
+                self.instruction(Instruction::BigFont { x })
+            }
+            "long" => {
+                let target = self.tokens.expect()?;
+                let target = self.target(target, 0xFFFF, "16 bits")?;
+                self.instruction(Instruction::LoadIndexLong)?;
+                let addr = self.resolve(target, FixupKind::Long);
+                self.emit(addr.to_be_bytes()[0])?;
+                self.emit(addr.to_be_bytes()[1])
+            }
+            _ => {
+                let target = self.target(operand, 0xFFF, "12 bits")?;
+                let addr = self.resolve(target, FixupKind::Addr);
+                self.instruction(Instruction::LoadIndex { addr })
+            }
+        }
+    }
+
+    fn jump(&mut self, instruction: fn(u16) -> Instruction) -> Result<(), AssembleError> {
+        let target = self.tokens.expect()?;
+        let target = self.target(target, 0xFFF, "12 bits")?;
+        let addr = self.resolve(target, FixupKind::Addr);
+        self.instruction(instruction(addr))
+    }
+
+    // :unpack n label, or :unpack long label
+    fn unpack(&mut self) -> Result<(), AssembleError> {
+        let long = self.tokens.next_if("long");
+        let high = if long { 0 } else { self.nibble()? << 4 };
+        let target = self.tokens.expect()?;
+        let target = if long {
+            self.target(target, 0xFFFF, "16 bits")?
+        } else {
+            self.target(target, 0xFFF, "12 bits")?
+        };
+        let [addr_high, addr_low] = self
+            .resolve(target, FixupKind::Unpack { long })
+            .to_be_bytes();
+
+        self.instruction(Instruction::LoadByte {
+            x: 0,
+            byte: high | addr_high,
+        })?;
+        self.instruction(Instruction::LoadByte {
+            x: 1,
+            byte: addr_low,
+        })
+    }
+
+    fn conditional(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        let condition = self.condition()?;
+        let keyword = self.tokens.expect()?;
+        match keyword.text {
+            "then" => self.skip(condition, false),
+            "begin" => {
+                self.skip(condition, true)?;
+                let jump = self.here;
+                self.instruction(Instruction::Jump { addr: 0 })?;
+                self.flow.push(Flow::If {
+                    token,
+                    jump,
+                    has_else: false,
+                });
+                Ok(())
+            }
+            _ => Err(keyword.error(format!(
+                "Expected `then` or `begin`, found `{}`",
+                keyword.text
+            ))),
+        }
+    }
+
+    fn else_branch(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        let Some(Flow::If { jump, has_else, .. }) = self.flow.last().cloned() else {
+            return Err(token.error("`else` without `if ... begin`"));
+        };
+        if has_else {
+            return Err(token.error("`else` is already used in this block"));
+        }
+
+        let skip_else = self.here;
+        self.instruction(Instruction::Jump { addr: 0 })?;
+        self.patch(jump, FixupKind::Addr, self.here)?;
+        if let Some(Flow::If { jump, has_else, .. }) = self.flow.last_mut() {
+            *jump = skip_else;
+            *has_else = true;
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        let Some(Flow::If { jump, .. }) = self.flow.last().cloned() else {
+            return Err(token.error("`end` without `if ... begin`"));
+        };
+        self.flow.pop();
+        self.patch(jump, FixupKind::Addr, self.here)
+    }
+
+    fn while_condition(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        if !self
+            .flow
+            .iter()
+            .any(|flow| matches!(flow, Flow::Loop { .. }))
+        {
+            return Err(token.error("`while` outside of `loop`"));
+        }
+
+        let condition = self.condition()?;
+        self.skip(condition, true)?;
+        let jump = self.here;
+        self.instruction(Instruction::Jump { addr: 0 })?;
+
+        // `while` may sit inside an `if` block, it leaves the innermost loop
+        if let Some(Flow::Loop { breaks, .. }) = self
+            .flow
+            .iter_mut()
+            .rev()
+            .find(|flow| matches!(flow, Flow::Loop { .. }))
+        {
+            breaks.push(jump);
+        }
+        Ok(())
+    }
+
+    fn again(&mut self, token: Token<'a>) -> Result<(), AssembleError> {
+        let Some(Flow::Loop { start, breaks, .. }) = self.flow.last().cloned() else {
+            return Err(token.error("`again` without `loop`"));
+        };
+        self.flow.pop();
+
+        self.instruction(Instruction::Jump { addr: 0 })?;
+        self.patch(self.here - 2, FixupKind::Addr, start)?;
+        for jump in breaks {
+            self.patch(jump, FixupKind::Addr, self.here)?;
+        }
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition, AssembleError> {
+        let x = self.register()?;
+        let op = self.tokens.expect()?;
+        let comparison = match op.text {
+            "key" => return Ok(Condition::Key { x, pressed: true }),
+            "-key" => return Ok(Condition::Key { x, pressed: false }),
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            ">" => Comparison::Greater,
+            "<=" => Comparison::LessEqual,
+            ">=" => Comparison::GreaterEqual,
+            _ => return Err(op.error(format!("Unknown condition `{}`", op.text))),
+        };
+
+        let operand = self.tokens.expect()?;
+        let operand = match self.register_of(operand) {
+            Some(y) => Operand::Register(y),
+            None => Operand::Byte(self.byte_of(operand)?),
+        };
+        if x == 0xF && !matches!(comparison, Comparison::Equal | Comparison::NotEqual) {
+            return Err(op.error("vF can't be compared with `<`, `>`, `<=` or `>=`"));
+        }
+
+        Ok(Condition::Compare {
+            x,
+            comparison,
+            operand,
+        })
+    }
+
+    /// Emits the instructions skipping the next one when the condition is `when`.
+    fn skip(&mut self, condition: Condition, when: bool) -> Result<(), AssembleError> {
+        let (x, comparison, operand) = match condition {
+            Condition::Key { x, pressed } => {
+                return self.instruction(if pressed == when {
+                    Instruction::SkipKey { x }
+                } else {
+                    Instruction::SkipNotKey { x }
+                });
+            }
+            Condition::Compare {
+                x,
+                comparison,
+                operand,
+            } => (x, comparison, operand),
+        };
+
+        let (sub, flag) = match comparison {
+            Comparison::Equal | Comparison::NotEqual => {
+                let equal = (comparison == Comparison::Equal) == when;
+                return self.instruction(match (operand, equal) {
+                    (Operand::Byte(byte), true) => Instruction::SkipEqualByte { x, byte },
+                    (Operand::Byte(byte), false) => Instruction::SkipNotEqualByte { x, byte },
+                    (Operand::Register(y), true) => Instruction::SkipEqual { x, y },
+                    (Operand::Register(y), false) => Instruction::SkipNotEqual { x, y },
+                });
+            }
+            // The comparisons subtract in vF, and read the borrow flag the subtraction leaves
+            // there: vF := y - x sets it when x <= y, vF := x - y when x >= y
+            Comparison::Greater => (Instruction::Sub { x: 0xF, y: x }, false),
+            Comparison::LessEqual => (Instruction::Sub { x: 0xF, y: x }, true),
+            Comparison::Less => (Instruction::SubN { x: 0xF, y: x }, false),
+            Comparison::GreaterEqual => (Instruction::SubN { x: 0xF, y: x }, true),
+        };
+
+        self.instruction(match operand {
+            Operand::Byte(byte) => Instruction::LoadByte { x: 0xF, byte },
+            Operand::Register(y) => Instruction::Move { x: 0xF, y },
+        })?;
+        self.instruction(sub)?;
+        self.instruction(Instruction::SkipEqualByte {
+            x: 0xF,
+            byte: u8::from(flag == when),
+        })
+    }
+
+    fn define_macro(&mut self) -> Result<(), AssembleError> {
+        let name = self.name()?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.tokens.expect()?;
+            if token.text == "{" {
+                break;
+            }
+            params.push(token.text);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.tokens.expect()?;
+            match token.text {
+                "{" => depth += 1,
+                "}" if depth == 0 => break,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            body.push(token);
+        }
+
+        self.macros.insert(name.text, Macro { params, body });
+        Ok(())
+    }
+
+    fn expand(&mut self, token: Token<'a>, m: &Macro<'a>) -> Result<(), AssembleError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(token.error("Too many macro expansions, is a macro invoking itself?"));
+        }
+
+        let args = m
+            .params
+            .iter()
+            .map(|_| self.tokens.expect())
+            .collect::<Result<Vec<_>, _>>()?;
+        let body = m.body.iter().map(|&token| {
+            m.params
+                .iter()
+                .position(|&param| param == token.text)
+                .map_or(token, |i| args[i])
+        });
+        self.tokens.push_front(body.collect::<Vec<_>>().into_iter());
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: Token<'a>, addr: usize) -> Result<(), AssembleError> {
+        if self.labels.contains_key(name.text) {
+            return Err(name.error(format!("Label `{}` is already defined", name.text)));
+        }
+        if addr > 0xFFFF {
+            return Err(name.error(format!("Address {addr:#x} is beyond 64 KiB")));
+        }
+        self.labels.insert(name.text, addr as u16);
+        Ok(())
+    }
+
+    /// Reads a new name for a label, a constant, an alias or a macro.
+    fn name(&mut self) -> Result<Token<'a>, AssembleError> {
+        let name = self.tokens.expect()?;
+        if RESERVED.contains(&name.text)
+            || parse_register(name.text).is_some()
+            || parse_number(name.text).is_some()
+        {
+            return Err(name.error(format!("`{}` can't be used as a name", name.text)));
+        }
+        Ok(name)
+    }
+
+    fn register(&mut self) -> Result<u8, AssembleError> {
+        let token = self.tokens.expect()?;
+        self.register_of(token)
+            .ok_or_else(|| token.error(format!("Expected a register, found `{}`", token.text)))
+    }
+
+    fn register_of(&self, token: Token) -> Option<u8> {
+        parse_register(token.text).or_else(|| self.aliases.get(token.text).copied())
+    }
+
+    fn byte(&mut self) -> Result<u8, AssembleError> {
+        let token = self.tokens.expect()?;
+        self.byte_of(token)
+    }
+
+    fn byte_of(&self, token: Token) -> Result<u8, AssembleError> {
+        to_byte(token, self.known_value(token)?)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AssembleError> {
+        let token = self.tokens.expect()?;
+        let value = self.known_value(token)?;
+        Ok(self.integer(token, value, 0xF, "a nibble")? as u8)
+    }
+
+    /// Checks that a value is between 0 and `max`, and rounds it down.
+    fn integer(
+        &self,
+        token: Token,
+        value: f64,
+        max: i64,
+        what: &str,
+    ) -> Result<i64, AssembleError> {
+        // NaN and infinities would otherwise saturate to a valid integer
+        let integer = value.floor() as i64;
+        if !value.is_finite() || !(0..=max).contains(&integer) {
+            return Err(token.error(format!("{value} doesn't fit in {what}")));
+        }
+        Ok(integer)
+    }
+
+    /// Returns the value of a number, a constant, a label or `HERE`.
+    fn value(&self, token: Token) -> Option<f64> {
+        match token.text {
+            "HERE" => Some(self.here as f64),
+            "PI" => Some(std::f64::consts::PI),
+            "E" => Some(std::f64::consts::E),
+            text => parse_number(text)
+                .or_else(|| self.constants.get(text).copied())
+                .or_else(|| self.labels.get(text).map(|&addr| addr as f64)),
+        }
+    }
+
+    fn known_value(&self, token: Token) -> Result<f64, AssembleError> {
+        self.value(token)
+            .ok_or_else(|| token.error(format!("Undefined name `{}`", token.text)))
+    }
+
+    fn is_label(&self, token: Token) -> bool {
+        self.labels.contains_key(token.text) && !self.constants.contains_key(token.text)
+    }
+
+    /// Reads an address of at most `max`, or the name of a label that isn't defined yet.
+    fn target(&self, token: Token<'a>, max: i64, what: &str) -> Result<Target<'a>, AssembleError> {
+        match self.value(token) {
+            Some(value) => Ok(Target::Known(self.integer(token, value, max, what)? as u16)),
+            None if parse_register(token.text).is_some() || RESERVED.contains(&token.text) => {
+                Err(token.error(format!("Expected an address, found `{}`", token.text)))
+            }
+            None => Ok(Target::Forward(token)),
+        }
+    }
+
+    /// Returns the address to emit for a target, recording a fixup for the instruction about
+    /// to be emitted at `here` when the label isn't defined yet.
+    fn resolve(&mut self, target: Target<'a>, kind: FixupKind) -> u16 {
+        match target {
+            Target::Known(addr) => addr,
+            Target::Forward(label) => {
+                self.fixups.push(Fixup {
+                    addr: self.here,
+                    kind,
+                    label,
+                });
+                0
+            }
+        }
+    }
+
+    /// Writes `target` into what was emitted at `addr`.
+    fn patch(&mut self, addr: usize, kind: FixupKind, target: usize) -> Result<(), AssembleError> {
+        let offset = addr - START_ADDR;
+        let fits = match kind {
+            FixupKind::Addr | FixupKind::Unpack { long: false } => target <= 0xFFF,
+            FixupKind::Long | FixupKind::Unpack { long: true } => target <= 0xFFFF,
+        };
+        if !fits {
+            return Err(self.statement.error(format!(
+                "Address {target:#x} is out of reach of this instruction"
+            )));
+        }
+
+        let [high, low] = (target as u16).to_be_bytes();
+        match kind {
+            FixupKind::Addr => {
+                self.rom[offset] = (self.rom[offset] & 0xF0) | high;
+                self.rom[offset + 1] = low;
+            }
+            FixupKind::Long => {
+                self.rom[offset] = high;
+                self.rom[offset + 1] = low;
+            }
+            FixupKind::Unpack { long } => {
+                self.rom[offset + 1] = if long {
+                    high
+                } else {
+                    (self.rom[offset + 1] & 0xF0) | high
+                };
+                self.rom[offset + 3] = low;
+            }
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, instruction: Instruction) -> Result<(), AssembleError> {
+        let [high, low] = instruction.encode().to_be_bytes();
+        self.emit(high)?;
+        self.emit(low)
+    }
+
+    fn emit(&mut self, byte: u8) -> Result<(), AssembleError> {
+        if self.here > 0xFFFF {
+            return Err(self.statement.error("The program doesn't fit in 64 KiB"));
+        }
+
+        let offset = self.here - START_ADDR;
+        if offset >= self.rom.len() {
+            self.rom.resize(offset + 1, 0);
+            self.written.resize(offset + 1, false);
+        }
+        if self.written[offset] {
+            return Err(self
+                .statement
+                .error(format!("Address {:#05x} is already assembled", self.here)));
+        }
+
+        self.rom[offset] = byte;
+        self.written[offset] = true;
+        self.here += 1;
+        Ok(())
+    }
+
+    /// Returns a byte assembled so far.
+    fn peek(&self, addr: f64) -> Option<u8> {
+        let offset = (addr as usize).checked_sub(START_ADDR)?;
+        self.rom.get(offset).copied()
+    }
+}
+
+/// Converts a value to a byte, where negative values down to -128 stand for their two's
+/// complement.
+fn to_byte(token: Token, value: f64) -> Result<u8, AssembleError> {
+    if !(-128.0..256.0).contains(&value) {
+        return Err(token.error(format!("{value} doesn't fit in a byte")));
+    }
+    Ok(value.floor() as i64 as u8)
+}
+
+/// Parses `v0` to `vF`, in either case.
+fn parse_register(text: &str) -> Option<u8> {
+    let digit = text.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+/// Parses a decimal, `0x` hexadecimal or `0b` binary number, optionally negative.
+fn parse_number(text: &str) -> Option<f64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()? as f64
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()? as f64
+    } else if digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompile;
+
+    const SOURCE: &str = "
+        :const SPEED 2
+        :alias x v1
+
+        : main
+          x := 0
+          loop
+            i := ball
+            sprite x v2 8
+            x += SPEED
+            if x > 60 then x := 0
+            if v3 key then step
+          while v4 != 5 again
+          jump main
+
+        : step
+          v4 += 1
+          return
+
+        : ball
+          0b00111100 0b01111110 0b11111111 0b11111111
+          0b11111111 0b11111111 0b01111110 0b00111100
+    ";
+
+    #[test]
+    fn round_trip() {
+        let program = assemble(SOURCE).unwrap();
+        let source = decompile(&program.rom);
+        assert_eq!(assemble(&source).unwrap().rom, program.rom);
+    }
+
+    #[test]
+    fn conditions() {
+        // vF := 60, vF -= v1 leaves the borrow flag set when v1 <= 60, which skips the body
+        let rom = assemble("if v1 > 60 then v0 := 1").unwrap().rom;
+        assert_eq!(rom, [0x6F, 0x3C, 0x8F, 0x15, 0x3F, 0x01, 0x60, 0x01]);
+
+        let rom = assemble("if v3 key then v0 := 1").unwrap().rom;
+        assert_eq!(rom, [0xE3, 0xA1, 0x60, 0x01]);
+
+        // The loop is left by the jump to 0x206 when v4 == 5
+        let rom = assemble("loop while v4 != 5 again").unwrap().rom;
+        assert_eq!(rom, [0x44, 0x05, 0x12, 0x06, 0x12, 0x00]);
+    }
+
+    #[test]
+    fn undefined_name() {
+        let err = assemble("v0 := 1\n  i := missing").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+        assert!(err.message.contains("missing"), "{err}");
+    }
+
+    #[test]
+    fn value_out_of_range() {
+        let err = assemble("i := 0x1000").unwrap_err();
+        assert_eq!(err.to_string(), "1:6: 4096 doesn't fit in 12 bits");
+
+        let err = assemble(":calc X { 0 / 0 }\ni := X").unwrap_err();
+        assert_eq!(err.to_string(), "2:6: NaN doesn't fit in 12 bits");
+    }
+}
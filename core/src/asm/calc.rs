@@ -0,0 +1,134 @@
+use super::{AssembleError, Assembler};
+
+impl Assembler<'_> {
+    /// Evaluates a `:calc` expression after its opening `{`, up to and including the closing
+    /// `}`.
+    ///
+    /// Like in Octo, operators have no precedence and are evaluated from right to left, so
+    /// `2 * 3 + 1` is 8. Parentheses group terms.
+    pub(super) fn calc(&mut self) -> Result<f64, AssembleError> {
+        let value = self.calc_expression()?;
+        self.tokens.expect_text("}")?;
+        Ok(value)
+    }
+
+    fn calc_expression(&mut self) -> Result<f64, AssembleError> {
+        let left = self.calc_term()?;
+        let Some(op) = self.tokens.peek().filter(|op| is_binary(op)) else {
+            return Ok(left);
+        };
+        self.tokens.next();
+        let right = self.calc_expression()?;
+        Ok(binary(op, left, right))
+    }
+
+    fn calc_term(&mut self) -> Result<f64, AssembleError> {
+        let token = self.tokens.expect()?;
+        match token.text {
+            "(" => {
+                let value = self.calc_expression()?;
+                self.tokens.expect_text(")")?;
+                Ok(value)
+            }
+            "@" => {
+                // Peeks at a byte already assembled
+                let addr = self.calc_term()?;
+                Ok(self.peek(addr).map_or(0.0, f64::from))
+            }
+            op if is_unary(op) => {
+                let value = self.calc_term()?;
+                Ok(unary(op, value))
+            }
+            _ => self
+                .value(token)
+                .ok_or_else(|| token.error(format!("Undefined name `{}`", token.text))),
+        }
+    }
+}
+
+fn is_unary(op: &str) -> bool {
+    matches!(
+        op,
+        "-" | "~"
+            | "!"
+            | "sin"
+            | "cos"
+            | "tan"
+            | "exp"
+            | "log"
+            | "abs"
+            | "sqrt"
+            | "sign"
+            | "ceil"
+            | "floor"
+    )
+}
+
+fn unary(op: &str, value: f64) -> f64 {
+    match op {
+        "-" => -value,
+        "~" => !(value as i64) as f64,
+        "!" => f64::from(value == 0.0),
+        "sin" => value.sin(),
+        "cos" => value.cos(),
+        "tan" => value.tan(),
+        "exp" => value.exp(),
+        "log" => value.ln(),
+        "abs" => value.abs(),
+        "sqrt" => value.sqrt(),
+        "sign" => value.signum(),
+        "ceil" => value.ceil(),
+        "floor" => value.floor(),
+        _ => unreachable!("unknown unary operator {op}"),
+    }
+}
+
+fn is_binary(op: &str) -> bool {
+    matches!(
+        op,
+        "-" | "+"
+            | "*"
+            | "/"
+            | "%"
+            | "&"
+            | "|"
+            | "^"
+            | "<<"
+            | ">>"
+            | "pow"
+            | "min"
+            | "max"
+            | "<"
+            | ">"
+            | "<="
+            | ">="
+            | "=="
+            | "!="
+    )
+}
+
+fn binary(op: &str, left: f64, right: f64) -> f64 {
+    let bits = |f: fn(i64, i64) -> i64| f(left as i64, right as i64) as f64;
+    match op {
+        "-" => left - right,
+        "+" => left + right,
+        "*" => left * right,
+        "/" => left / right,
+        "%" => left % right,
+        "&" => bits(|a, b| a & b),
+        "|" => bits(|a, b| a | b),
+        "^" => bits(|a, b| a ^ b),
+        "<<" => bits(|a, b| a.wrapping_shl(b as u32)),
+        ">>" => bits(|a, b| a.wrapping_shr(b as u32)),
+        "pow" => left.powf(right),
+        "min" => left.min(right),
+        "max" => left.max(right),
+        "<" => f64::from(left < right),
+        ">" => f64::from(left > right),
+        "<=" => f64::from(left <= right),
+        ">=" => f64::from(left >= right),
+        "==" => f64::from(left == right),
+        "!=" => f64::from(left != right),
+        _ => unreachable!("unknown binary operator {op}"),
+    }
+}
@@ -0,0 +1,114 @@
+use super::AssembleError;
+
+/// A word of the source. Octo separates every token with whitespace, so `v0 := 1` is three
+/// tokens and `v0:=1` is one.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Token<'a> {
+    pub text: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token<'_> {
+    pub fn error(&self, message: impl Into<String>) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+/// Splits the source into tokens, dropping `#` comments. Lines and columns are 1-based and
+/// columns count characters.
+pub(super) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+
+    for (line, text) in source.lines().enumerate() {
+        let code = text.find('#').map_or(text, |comment| &text[..comment]);
+        let mut start = None;
+        let chars = code
+            .char_indices()
+            .chain(std::iter::once((code.len(), ' ')));
+
+        for (column, (i, c)) in chars.enumerate() {
+            if !c.is_whitespace() {
+                start.get_or_insert((i, column));
+            } else if let Some((start, start_column)) = start.take() {
+                tokens.push(Token {
+                    text: &code[start..i],
+                    line: line + 1,
+                    column: start_column + 1,
+                });
+            }
+        }
+    }
+
+    tokens
+}
+
+/// The tokens left to assemble. Macro expansions are pushed in front of them.
+#[derive(Debug)]
+pub(super) struct Tokens<'a> {
+    // Reversed, so the next token is popped from the end
+    stack: Vec<Token<'a>>,
+    // Where the last token was, to report a missing operand at the end of the source
+    last: Token<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(mut tokens: Vec<Token<'a>>) -> Self {
+        tokens.reverse();
+        Self {
+            stack: tokens,
+            last: Token {
+                text: "",
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    pub fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.stack.pop()?;
+        self.last = token;
+        Some(token)
+    }
+
+    /// Returns the next token, failing at the end of the source.
+    pub fn expect(&mut self) -> Result<Token<'a>, AssembleError> {
+        self.next().ok_or_else(|| {
+            let mut end = self.last;
+            end.column += end.text.chars().count();
+            end.error("Unexpected end of source")
+        })
+    }
+
+    /// Consumes the next token if its text is `text`.
+    pub fn next_if(&mut self, text: &str) -> bool {
+        if self.peek() == Some(text) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the next token, failing unless its text is `text`.
+    pub fn expect_text(&mut self, text: &str) -> Result<Token<'a>, AssembleError> {
+        let token = self.expect()?;
+        if token.text != text {
+            return Err(token.error(format!("Expected `{text}`, found `{}`", token.text)));
+        }
+        Ok(token)
+    }
+
+    pub fn peek(&self) -> Option<&'a str> {
+        self.stack.last().map(|token| token.text)
+    }
+
+    /// Inserts tokens before the remaining ones.
+    pub fn push_front(&mut self, tokens: impl DoubleEndedIterator<Item = Token<'a>>) {
+        self.stack.extend(tokens.rev());
+    }
+}
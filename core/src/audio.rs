@@ -0,0 +1,212 @@
+use crate::{AUDIO_PATTERN_SIZE, Chip8, DEFAULT_PITCH};
+use std::f32::consts::TAU;
+
+const TIMER_HZ: u32 = 60;
+const DEFAULT_FREQUENCY: f32 = 440.0;
+const DEFAULT_VOLUME: f32 = 0.25;
+// XO-CHIP plays the audio pattern at 4000 bits per second at the default pitch
+const PATTERN_RATE: f32 = 4000.0;
+const PATTERN_BITS: f32 = (AUDIO_PATTERN_SIZE * 8) as f32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Triangle,
+    Sawtooth,
+    Sine,
+}
+
+impl Waveform {
+    /// Returns the sample at `phase`, in the range `0.0..1.0`, between `-1.0` and `1.0`.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Self::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Self::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Self::Sawtooth => 2.0 * phase - 1.0,
+            Self::Sine => (TAU * phase).sin(),
+        }
+    }
+}
+
+/// Renders the buzzer of a [`Chip8`] to mono PCM samples.
+///
+/// While the sound timer is running, the renderer plays the XO-CHIP audio pattern at the
+/// frequency set by the pitch register, or a plain tone when no pattern has been loaded.
+/// The phase carries over between calls, so consecutive buffers join without clicks.
+#[derive(Debug, Clone)]
+pub struct AudioRenderer {
+    sample_rate: u32,
+    waveform: Waveform,
+    frequency: f32,
+    volume: f32,
+    phase: f32,
+}
+
+impl AudioRenderer {
+    #[must_use]
+    pub const fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            waveform: Waveform::Square,
+            frequency: DEFAULT_FREQUENCY,
+            volume: DEFAULT_VOLUME,
+            phase: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    /// Sets the frequency of the tone in hertz.
+    #[must_use]
+    pub const fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the amplitude of the output between `0.0` and `1.0`.
+    #[must_use]
+    pub const fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[must_use]
+    pub const fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    #[must_use]
+    pub const fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    #[must_use]
+    pub const fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Returns the number of samples that last for one 60 Hz timer tick.
+    #[must_use]
+    pub const fn samples_per_frame(&self) -> usize {
+        (self.sample_rate / TIMER_HZ) as usize
+    }
+
+    /// Fills `samples` with the current output of the buzzer.
+    pub fn render(&mut self, chip8: &Chip8, samples: &mut [f32]) {
+        if !chip8.is_buzzer_active() {
+            samples.fill(0.0);
+            self.phase = 0.0;
+            return;
+        }
+
+        let pattern = chip8.audio_pattern();
+        if pattern.iter().all(|&byte| byte == 0) {
+            let step = self.frequency / self.sample_rate as f32;
+            for sample in samples {
+                *sample = self.waveform.sample(self.phase) * self.volume;
+                self.phase = (self.phase + step).fract();
+            }
+        } else {
+            let pitch = (chip8.pitch() as f32 - DEFAULT_PITCH as f32) / 48.0;
+            let step = PATTERN_RATE * pitch.exp2() / self.sample_rate as f32;
+            for sample in samples {
+                let bit = self.phase as usize;
+                let high = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                *sample = if high { self.volume } else { -self.volume };
+                self.phase = (self.phase + step) % PATTERN_BITS;
+            }
+        }
+    }
+
+    /// Renders the samples for one 60 Hz timer tick.
+    #[must_use]
+    pub fn render_frame(&mut self, chip8: &Chip8) -> Vec<f32> {
+        let mut samples = vec![0.0; self.samples_per_frame()];
+        self.render(chip8, &mut samples);
+        samples
+    }
+}
+
+impl Chip8 {
+    /// Returns whether the buzzer sounded during the last frame, which is when the sound timer
+    /// was non-zero as the timers were ticked.
+    ///
+    /// Hosts render the audio of a frame after [`Chip8::run_frame`], so even a sound timer set
+    /// to 1 is heard for one frame.
+    #[must_use]
+    pub const fn is_buzzer_active(&self) -> bool {
+        self.buzzer_active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Starts the sound timer, optionally after loading the audio pattern from 0x300, and loops
+    fn beep(pattern: bool) -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.set_quirks(crate::Platform::XoChip.quirks());
+        chip8
+            .load(&[0xA3, 0x00, 0xF0, 0x02, 0x60, 0x02, 0xF0, 0x18, 0x12, 0x08])
+            .unwrap();
+        chip8.memory_mut()[0x300..0x310].fill(0xF0);
+        if !pattern {
+            chip8.set_pc(0x204);
+        }
+        chip8.run_frame(4).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn silent_until_sound_timer() {
+        let chip8 = Chip8::new();
+        assert!(!chip8.is_buzzer_active());
+
+        let mut renderer = AudioRenderer::new(48_000);
+        assert_eq!(renderer.samples_per_frame(), 800);
+        assert!(renderer.render_frame(&chip8).iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn tone() {
+        let chip8 = beep(false);
+        assert!(chip8.is_buzzer_active());
+
+        // 1 kHz at 8 kHz is 8 samples per period
+        let mut renderer = AudioRenderer::new(8_000)
+            .with_frequency(1000.0)
+            .with_volume(0.5);
+        let samples = renderer.render_frame(&chip8);
+        assert_eq!(samples[..4], [0.5; 4]);
+        assert_eq!(samples[4..8], [-0.5; 4]);
+        assert_eq!(samples[8], 0.5);
+    }
+
+    #[test]
+    fn pattern() {
+        let chip8 = beep(true);
+        assert!(chip8.is_buzzer_active());
+
+        // At the default pitch a 4 kHz output plays one bit per sample
+        let mut renderer = AudioRenderer::new(4_000).with_volume(1.0);
+        let samples = renderer.render_frame(&chip8);
+        assert_eq!(samples[..8], [1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0]);
+    }
+}
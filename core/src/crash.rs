@@ -0,0 +1,244 @@
+use crate::{Chip8, Instruction, Registers};
+use std::fmt;
+use std::io::{self, Write};
+
+// Number of executed instructions kept for crash reports
+pub(crate) const HISTORY_SIZE: usize = 16;
+
+/// The reason an instruction failed to execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UndefinedInstruction,
+    StackOverflow,
+    StackUnderflow,
+    /// The instruction accessed an address outside of the variant's memory.
+    MemoryOutOfBounds {
+        addr: usize,
+    },
+    /// The instruction checked a key number above `F`.
+    InvalidKey {
+        key: u8,
+    },
+    /// The instruction selected a font digit above `F`.
+    InvalidDigit {
+        digit: u8,
+    },
+}
+
+/// An instruction fetched and executed by the interpreter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Executed {
+    pub pc: u16,
+    pub opcode: u16,
+}
+
+/// An error raised by [`Chip8::emulate`], with the state of the machine when it happened.
+///
+/// `Display` prints a one line summary, and the alternate form (`{:#}`) prints a full crash
+/// report with the registers and the instructions that led to the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecuteError {
+    kind: ErrorKind,
+    pc: u16,
+    opcode: Option<u16>,
+    registers: Registers,
+    history: Vec<Executed>,
+}
+
+impl ExecuteError {
+    #[must_use]
+    pub const fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the address of the faulting instruction.
+    #[must_use]
+    pub const fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Returns the faulting opcode, or `None` when the instruction couldn't be fetched.
+    #[must_use]
+    pub const fn opcode(&self) -> Option<u16> {
+        self.opcode
+    }
+
+    /// Returns the number of return addresses on the stack.
+    #[must_use]
+    pub const fn stack_depth(&self) -> u8 {
+        self.registers.sp
+    }
+
+    /// Returns the registers as they were when the error was raised.
+    #[must_use]
+    pub const fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Returns the last executed instructions, oldest first. The faulting instruction is the
+    /// last one unless it couldn't be fetched.
+    #[must_use]
+    pub fn history(&self) -> &[Executed] {
+        &self.history
+    }
+
+    /// Writes the crash report, for example to a file.
+    pub fn write_report(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "{self:#}")
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UndefinedInstruction => write!(f, "Undefined instruction"),
+            Self::StackOverflow => write!(f, "Stack overflow"),
+            Self::StackUnderflow => write!(f, "Stack underflow"),
+            Self::MemoryOutOfBounds { addr } => {
+                write!(f, "Memory access out of bounds at {addr:#05x}")
+            }
+            Self::InvalidKey { key } => write!(f, "Invalid key {key:#04x}"),
+            Self::InvalidDigit { digit } => write!(f, "Invalid font digit {digit:#04x}"),
+        }
+    }
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} by ", self.kind)?;
+        match self.opcode {
+            Some(opcode) => write!(f, "{opcode:04X} at {:#05x}", self.pc)?,
+            None => write!(f, "fetch at {:#05x}", self.pc)?,
+        }
+        if !f.alternate() {
+            return Ok(());
+        }
+
+        let registers = &self.registers;
+        writeln!(f)?;
+        writeln!(f)?;
+        for (row, values) in registers.v.chunks(8).enumerate() {
+            write!(f, "V{:X}-V{:X}:", row * 8, row * 8 + 7)?;
+            for value in values {
+                write!(f, " {value:02X}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "I: {:#05x}  DT: {:02X}  ST: {:02X}",
+            registers.index, registers.delay_timer, registers.sound_timer
+        )?;
+        write!(f, "Stack depth: {}", registers.sp)?;
+        for addr in registers.call_stack().iter().rev() {
+            write!(f, "\n  0x{addr:03X}")?;
+        }
+
+        write!(f, "\n\nRecent instructions, oldest first:")?;
+        for (i, executed) in self.history.iter().enumerate() {
+            let marker = if i + 1 == self.history.len() && self.opcode.is_some() {
+                '>'
+            } else {
+                ' '
+            };
+            write!(
+                f,
+                "\n{marker} 0x{:03X}  {:04X}  {}",
+                executed.pc,
+                executed.opcode,
+                Instruction::decode(executed.opcode)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ExecuteError {}
+
+impl Chip8 {
+    /// Returns the last executed instructions, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = Executed> + '_ {
+        self.history.iter().copied()
+    }
+
+    pub(crate) fn record_history(&mut self, pc: u16, opcode: u16) {
+        if self.history.len() == HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(Executed { pc, opcode });
+    }
+
+    /// Attaches the state of the machine to an error raised by the current instruction.
+    pub(crate) fn error(&self, kind: ErrorKind, opcode: Option<u16>) -> ExecuteError {
+        ExecuteError {
+            kind,
+            pc: self.instruction_pc,
+            opcode,
+            registers: self.registers(),
+            history: self.history.iter().copied().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crash(program: &[u16]) -> ExecuteError {
+        let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+        let mut chip8 = Chip8::new();
+        chip8.load(&rom).unwrap();
+        (0..1000)
+            .find_map(|_| chip8.emulate().err())
+            .expect("program should crash")
+    }
+
+    #[test]
+    fn context() {
+        // Calls a subroutine made of an undefined instruction
+        let err = crash(&[0x6142, 0x2206, 0x1202, 0x0000]);
+        assert_eq!(err.kind(), ErrorKind::UndefinedInstruction);
+        assert_eq!(err.pc(), 0x206);
+        assert_eq!(err.opcode(), Some(0x0000));
+        assert_eq!(err.stack_depth(), 1);
+        assert_eq!(err.registers().v[1], 0x42);
+        assert_eq!(err.registers().call_stack(), [0x204]);
+        assert_eq!(
+            err.history(),
+            [
+                Executed {
+                    pc: 0x200,
+                    opcode: 0x6142
+                },
+                Executed {
+                    pc: 0x202,
+                    opcode: 0x2206
+                },
+                Executed {
+                    pc: 0x206,
+                    opcode: 0x0000
+                },
+            ]
+        );
+
+        assert_eq!(err.to_string(), "Undefined instruction by 0000 at 0x206");
+        let report = format!("{err:#}");
+        assert!(report.contains("V0-V7: 00 42 00 00 00 00 00 00"));
+        assert!(report.contains("Stack depth: 1\n  0x204"));
+        assert!(report.ends_with("> 0x206  0000  SYS 0x000"));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        // Counts V0 up to 20 before running into undefined memory
+        let err = crash(&[0x7001, 0x3014, 0x1200]);
+        assert_eq!(err.history().len(), HISTORY_SIZE);
+        assert_eq!(
+            err.history().last(),
+            Some(&Executed {
+                pc: 0x206,
+                opcode: 0x0000
+            })
+        );
+    }
+}
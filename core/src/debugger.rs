@@ -0,0 +1,483 @@
+use crate::{Chip8, ExecuteError, Instruction, RewindBuffer, WatchHit};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Upper bound on the instructions a single step-over, step-out or run-until-frame may
+/// execute, so that a subroutine that never returns can't hang the caller.
+const STEP_LIMIT: u32 = 1_000_000;
+
+/// Drives a [`Chip8`] one instruction at a time and stops on breakpoints.
+///
+/// The debugger ends a frame like [`Chip8::run_frame`] once `instructions_per_frame`
+/// instructions have run since the timers were last ticked or a draw waits for the display,
+/// so stepping through a program keeps the timers running at the same rate as the host would.
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    opcode_breakpoints: Vec<OpcodePattern>,
+    instructions_per_frame: u32,
+    resume_pc: Option<u16>,
+}
+
+/// Why a debugger run returned control to the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The requested step completed.
+    Step,
+    /// The next instruction is at a PC breakpoint.
+    Breakpoint(u16),
+    /// The next instruction matches an opcode breakpoint.
+    OpcodeBreakpoint { addr: u16, opcode: u16 },
+    /// The last instruction made a memory access that hit a pausing watchpoint.
+    Watchpoint(WatchHit),
+    /// The timers were ticked at the end of a frame.
+    FrameEnd,
+    /// The program executed `00FD`.
+    Halted,
+    /// The step executed the maximum number of instructions without completing.
+    StepLimit,
+}
+
+impl Debugger {
+    #[must_use]
+    pub const fn new(instructions_per_frame: u32) -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            opcode_breakpoints: Vec::new(),
+            instructions_per_frame: if instructions_per_frame == 0 {
+                1
+            } else {
+                instructions_per_frame
+            },
+            resume_pc: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Sets the number of instructions per frame, for example to replay a movie recorded at
+    /// another rate. 0 is treated as 1.
+    pub const fn set_instructions_per_frame(&mut self, instructions_per_frame: u32) {
+        self.instructions_per_frame = if instructions_per_frame == 0 {
+            1
+        } else {
+            instructions_per_frame
+        };
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Adds a breakpoint at `addr` or removes the one already there, returning whether a
+    /// breakpoint is now set.
+    pub fn toggle_breakpoint(&mut self, addr: u16) -> bool {
+        if self.breakpoints.remove(&addr) {
+            false
+        } else {
+            self.breakpoints.insert(addr);
+            true
+        }
+    }
+
+    #[must_use]
+    pub fn has_breakpoint(&self, addr: u16) -> bool {
+        self.breakpoints.contains(&addr)
+    }
+
+    /// Returns the PC breakpoints in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_opcode_breakpoint(&mut self, pattern: OpcodePattern) {
+        if !self.opcode_breakpoints.contains(&pattern) {
+            self.opcode_breakpoints.push(pattern);
+        }
+    }
+
+    pub fn remove_opcode_breakpoint(&mut self, pattern: OpcodePattern) {
+        self.opcode_breakpoints.retain(|&other| other != pattern);
+    }
+
+    #[must_use]
+    pub fn opcode_breakpoints(&self) -> &[OpcodePattern] {
+        &self.opcode_breakpoints
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.opcode_breakpoints.clear();
+    }
+
+    /// Returns the instruction at the PC, which will be executed next.
+    #[must_use]
+    pub const fn next_instruction(chip8: &Chip8) -> Instruction {
+        Instruction::decode(chip8.opcode_at(chip8.pc))
+    }
+
+    /// Checks the breakpoints against the next instruction, for hosts that call
+    /// [`Chip8::emulate`] themselves.
+    ///
+    /// After a stop is reported, the next check at the same address passes so that the host can
+    /// resume from the breakpoint.
+    pub fn check(&mut self, chip8: &Chip8) -> Option<StopReason> {
+        if self.resume_pc.take() == Some(chip8.pc) {
+            return None;
+        }
+
+        let reason = self.breakpoint(chip8);
+        if reason.is_some() {
+            self.resume_pc = Some(chip8.pc);
+        }
+        reason
+    }
+
+    /// Executes a single instruction, entering subroutines.
+    pub fn step_into(&mut self, chip8: &mut Chip8) -> Result<StopReason, ExecuteError> {
+        self.resume_pc = None;
+        if chip8.halted {
+            return Ok(StopReason::Halted);
+        }
+
+        self.execute(chip8)?;
+        Ok(chip8
+            .watch_pause()
+            .map_or(StopReason::Step, StopReason::Watchpoint))
+    }
+
+    /// Executes a single instruction, running a `2nnn` call until it returns.
+    pub fn step_over(&mut self, chip8: &mut Chip8) -> Result<StopReason, ExecuteError> {
+        let Instruction::Call { .. } = Self::next_instruction(chip8) else {
+            return self.step_into(chip8);
+        };
+
+        let sp = chip8.sp;
+        let return_pc = chip8.pc.wrapping_add(2);
+        self.run_until(chip8, false, |chip8, _| {
+            chip8.sp == sp && chip8.pc == return_pc
+        })
+    }
+
+    /// Runs until the current subroutine returns with `00EE`.
+    ///
+    /// Outside of a subroutine this is the same as [`Debugger::step_into`].
+    pub fn step_out(&mut self, chip8: &mut Chip8) -> Result<StopReason, ExecuteError> {
+        if chip8.sp == 0 {
+            return self.step_into(chip8);
+        }
+
+        let sp = chip8.sp;
+        self.run_until(chip8, false, |chip8, _| chip8.sp < sp)
+    }
+
+    /// Moves back to the state before the last executed instruction, by rewinding to an older
+    /// snapshot and running forward from it.
+    ///
+    /// Returns `false` and leaves the machine untouched when `rewind` doesn't reach back far
+    /// enough. The replayed instructions don't stop at breakpoints.
+    pub fn step_back(
+        &mut self,
+        chip8: &mut Chip8,
+        rewind: &mut RewindBuffer,
+    ) -> Result<bool, ExecuteError> {
+        let Some(target) = chip8.cycles().checked_sub(1) else {
+            return Ok(false);
+        };
+        if !rewind.rewind_to_cycle(chip8, target) {
+            return Ok(false);
+        }
+
+        for _ in 0..STEP_LIMIT {
+            if chip8.cycles() >= target || chip8.halted {
+                break;
+            }
+            self.execute(chip8)?;
+        }
+        // Continuing shouldn't stop again at a breakpoint on the instruction stepped back to
+        self.resume_pc = Some(chip8.pc);
+        Ok(true)
+    }
+
+    /// Runs until the end of the current frame, when the timers are ticked.
+    pub fn run_until_frame(&mut self, chip8: &mut Chip8) -> Result<StopReason, ExecuteError> {
+        let reason = self.run_until(chip8, false, |_, frame_ended| frame_ended)?;
+        Ok(match reason {
+            StopReason::Step => StopReason::FrameEnd,
+            reason => reason,
+        })
+    }
+
+    /// Runs the rest of the current frame like [`Chip8::run_frame`], for hosts that run the
+    /// machine continuously under the debugger.
+    ///
+    /// Unlike the stepping methods, this stops before the first instruction too when it is at a
+    /// breakpoint, unless the last run stopped there.
+    pub fn run_frame(&mut self, chip8: &mut Chip8) -> Result<StopReason, ExecuteError> {
+        let resuming = self.resume_pc == Some(chip8.pc);
+        let reason = self.run_until(chip8, !resuming, |_, frame_ended| frame_ended)?;
+        Ok(match reason {
+            StopReason::Step => StopReason::FrameEnd,
+            StopReason::Halted => {
+                chip8.tick_timers();
+                StopReason::FrameEnd
+            }
+            reason => reason,
+        })
+    }
+
+    /// Executes instructions until `done` holds after one of them or a breakpoint or
+    /// watchpoint is reached. `done` is also told whether the instruction ended a frame.
+    ///
+    /// The breakpoints are only checked before the first instruction when `check_first` is set,
+    /// so that a step can continue from a breakpoint.
+    fn run_until(
+        &mut self,
+        chip8: &mut Chip8,
+        check_first: bool,
+        done: impl Fn(&Chip8, bool) -> bool,
+    ) -> Result<StopReason, ExecuteError> {
+        self.resume_pc = None;
+
+        for cycle in 0..STEP_LIMIT {
+            if chip8.halted {
+                return Ok(StopReason::Halted);
+            }
+
+            if (check_first || cycle > 0)
+                && let Some(reason) = self.breakpoint(chip8)
+            {
+                self.resume_pc = Some(chip8.pc);
+                return Ok(reason);
+            }
+
+            let frame_ended = self.execute(chip8)?;
+            if let Some(hit) = chip8.watch_pause() {
+                return Ok(StopReason::Watchpoint(hit));
+            }
+            if done(chip8, frame_ended) {
+                return Ok(StopReason::Step);
+            }
+        }
+
+        Ok(StopReason::StepLimit)
+    }
+
+    /// Executes one instruction and ticks the timers at the end of a frame, returning whether
+    /// the frame ended.
+    fn execute(&mut self, chip8: &mut Chip8) -> Result<bool, ExecuteError> {
+        chip8.emulate()?;
+
+        if chip8.frame_cycles() < self.instructions_per_frame && !chip8.display_waiting {
+            return Ok(false);
+        }
+
+        chip8.tick_timers();
+        Ok(true)
+    }
+
+    fn breakpoint(&self, chip8: &Chip8) -> Option<StopReason> {
+        let addr = chip8.pc;
+        if self.breakpoints.contains(&addr) {
+            return Some(StopReason::Breakpoint(addr));
+        }
+
+        let opcode = chip8.opcode_at(addr);
+        self.opcode_breakpoints
+            .iter()
+            .any(|pattern| pattern.matches(opcode))
+            .then_some(StopReason::OpcodeBreakpoint { addr, opcode })
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Step => write!(f, "Step"),
+            Self::Breakpoint(addr) => write!(f, "Breakpoint at {addr:#05x}"),
+            Self::OpcodeBreakpoint { addr, opcode } => {
+                write!(f, "Opcode breakpoint {opcode:04X} at {addr:#05x}")
+            }
+            Self::Watchpoint(hit) => write!(f, "Watchpoint: {hit}"),
+            Self::FrameEnd => write!(f, "End of frame"),
+            Self::Halted => write!(f, "Halted"),
+            Self::StepLimit => write!(f, "Step limit reached"),
+        }
+    }
+}
+
+/// Matches opcodes whose bits under `mask` equal `value`.
+///
+/// Patterns are usually parsed from the notation used in the instruction comments, where hex
+/// digits are fixed and any other character is a wildcard nibble, e.g. `Dxyn`, `8xy6` or `F_33`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodePattern {
+    value: u16,
+    mask: u16,
+}
+
+impl OpcodePattern {
+    #[must_use]
+    pub const fn new(value: u16, mask: u16) -> Self {
+        Self {
+            value: value & mask,
+            mask,
+        }
+    }
+
+    #[must_use]
+    pub const fn matches(&self, opcode: u16) -> bool {
+        opcode & self.mask == self.value
+    }
+}
+
+impl fmt::Display for OpcodePattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for shift in [12, 8, 4, 0] {
+            if (self.mask >> shift) & 0xF == 0xF {
+                write!(f, "{:X}", (self.value >> shift) & 0xF)?;
+            } else {
+                write!(f, "_")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for OpcodePattern {
+    type Err = ParseOpcodePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.chars().count() != 4 {
+            return Err(ParseOpcodePatternError(s.to_string()));
+        }
+
+        let (value, mask) = s
+            .chars()
+            .fold((0, 0), |(value, mask), c| match c.to_digit(16) {
+                Some(digit) => ((value << 4) | digit as u16, (mask << 4) | 0xF),
+                None => (value << 4, mask << 4),
+            });
+
+        Ok(Self::new(value, mask))
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseOpcodePatternError(String);
+
+impl fmt::Display for ParseOpcodePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid opcode pattern {:?}, expected four nibbles",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseOpcodePatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Calls a subroutine that sets V1 and V2, then sets V0 and loops forever
+    const ROM: [u8; 14] = [
+        0x22, 0x08, 0x60, 0x01, 0x12, 0x04, 0x00, 0x00, 0x61, 0x02, 0x62, 0x03, 0x00, 0xEE,
+    ];
+
+    fn chip8() -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.load(&ROM).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn step_over() {
+        let mut chip8 = chip8();
+        let mut debugger = Debugger::new(100);
+
+        assert_eq!(debugger.step_over(&mut chip8).unwrap(), StopReason::Step);
+        assert_eq!(chip8.pc(), 0x202);
+        assert_eq!(chip8.register(1), 2);
+        assert_eq!(chip8.register(2), 3);
+
+        // Anything but a call is a single step
+        assert_eq!(debugger.step_over(&mut chip8).unwrap(), StopReason::Step);
+        assert_eq!(chip8.pc(), 0x204);
+        assert_eq!(chip8.register(0), 1);
+    }
+
+    #[test]
+    fn step_out() {
+        let mut chip8 = chip8();
+        let mut debugger = Debugger::new(100);
+
+        debugger.step_into(&mut chip8).unwrap();
+        debugger.step_into(&mut chip8).unwrap();
+        assert_eq!(chip8.pc(), 0x20A);
+        assert_eq!(chip8.register(2), 0);
+
+        assert_eq!(debugger.step_out(&mut chip8).unwrap(), StopReason::Step);
+        assert_eq!(chip8.pc(), 0x202);
+        assert_eq!(chip8.register(2), 3);
+    }
+
+    #[test]
+    fn step_over_stops_at_breakpoint() {
+        let mut chip8 = chip8();
+        let mut debugger = Debugger::new(100);
+        debugger.add_breakpoint(0x20A);
+
+        assert_eq!(
+            debugger.step_over(&mut chip8).unwrap(),
+            StopReason::Breakpoint(0x20A)
+        );
+        assert_eq!(chip8.pc(), 0x20A);
+    }
+
+    #[test]
+    fn resume_from_breakpoint() {
+        let mut chip8 = chip8();
+        let mut debugger = Debugger::new(100);
+        debugger.add_breakpoint(0x200);
+        debugger.add_opcode_breakpoint("6__3".parse().unwrap());
+
+        // The first run stops before executing anything
+        assert_eq!(
+            debugger.run_frame(&mut chip8).unwrap(),
+            StopReason::Breakpoint(0x200)
+        );
+        assert_eq!(chip8.cycles(), 0);
+
+        assert_eq!(
+            debugger.run_frame(&mut chip8).unwrap(),
+            StopReason::OpcodeBreakpoint {
+                addr: 0x20A,
+                opcode: 0x6203
+            }
+        );
+        assert_eq!(chip8.register(2), 0);
+
+        assert_eq!(
+            debugger.run_frame(&mut chip8).unwrap(),
+            StopReason::FrameEnd
+        );
+        assert_eq!(chip8.register(2), 3);
+        assert_eq!(chip8.pc(), 0x204);
+    }
+}
@@ -0,0 +1,466 @@
+use std::fmt;
+
+/// A decoded CHIP-8, SUPER-CHIP or XO-CHIP instruction.
+///
+/// Register operands are register numbers (`x` is the `x` nibble of the opcode), not values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// `0nnn`: call a machine code routine.
+    Sys { addr: u16 },
+    /// `00E0`
+    ClearScreen,
+    /// `00EE`
+    Return,
+    /// `00Cn`
+    ScrollDown { n: u8 },
+    /// `00Dn`
+    ScrollUp { n: u8 },
+    /// `00FB`
+    ScrollRight,
+    /// `00FC`
+    ScrollLeft,
+    /// `00FD`
+    Exit,
+    /// `00FE`
+    Lores,
+    /// `00FF`
+    Hires,
+    /// `1nnn`
+    Jump { addr: u16 },
+    /// `2nnn`
+    Call { addr: u16 },
+    /// `3xkk`
+    SkipEqualByte { x: u8, byte: u8 },
+    /// `4xkk`
+    SkipNotEqualByte { x: u8, byte: u8 },
+    /// `5xy0`
+    SkipEqual { x: u8, y: u8 },
+    /// `5xy2`
+    SaveRange { x: u8, y: u8 },
+    /// `5xy3`
+    LoadRange { x: u8, y: u8 },
+    /// `6xkk`
+    LoadByte { x: u8, byte: u8 },
+    /// `7xkk`
+    AddByte { x: u8, byte: u8 },
+    /// `8xy0`
+    Move { x: u8, y: u8 },
+    /// `8xy1`
+    Or { x: u8, y: u8 },
+    /// `8xy2`
+    And { x: u8, y: u8 },
+    /// `8xy3`
+    Xor { x: u8, y: u8 },
+    /// `8xy4`
+    Add { x: u8, y: u8 },
+    /// `8xy5`
+    Sub { x: u8, y: u8 },
+    /// `8xy6`
+    ShiftRight { x: u8, y: u8 },
+    /// `8xy7`
+    SubN { x: u8, y: u8 },
+    /// `8xyE`
+    ShiftLeft { x: u8, y: u8 },
+    /// `9xy0`
+    SkipNotEqual { x: u8, y: u8 },
+    /// `Annn`
+    LoadIndex { addr: u16 },
+    /// `Bnnn`
+    JumpOffset { addr: u16 },
+    /// `Cxkk`
+    Random { x: u8, byte: u8 },
+    /// `Dxyn`
+    Draw { x: u8, y: u8, n: u8 },
+    /// `Ex9E`
+    SkipKey { x: u8 },
+    /// `ExA1`
+    SkipNotKey { x: u8 },
+    /// `F000 nnnn`: the address is stored in the word following the opcode.
+    LoadIndexLong,
+    /// `Fn01`
+    Plane { n: u8 },
+    /// `F002`
+    Audio,
+    /// `Fx07`
+    LoadDelay { x: u8 },
+    /// `Fx0A`
+    WaitKey { x: u8 },
+    /// `Fx15`
+    SetDelay { x: u8 },
+    /// `Fx18`
+    SetSound { x: u8 },
+    /// `Fx1E`
+    AddIndex { x: u8 },
+    /// `Fx29`
+    Font { x: u8 },
+    /// `Fx30`
+    BigFont { x: u8 },
+    /// `Fx33`
+    Bcd { x: u8 },
+    /// `Fx3A`
+    SetPitch { x: u8 },
+    /// `Fx55`
+    Store { x: u8 },
+    /// `Fx65`
+    Restore { x: u8 },
+    /// `Fx75`
+    SaveFlags { x: u8 },
+    /// `Fx85`
+    LoadFlags { x: u8 },
+    /// Any opcode not defined by the supported instruction sets.
+    Unknown(u16),
+}
+
+impl Instruction {
+    #[must_use]
+    pub const fn decode(opcode: u16) -> Self {
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+        let n = (opcode & 0x000F) as u8;
+        let byte = (opcode & 0x00FF) as u8;
+        let addr = opcode & 0x0FFF;
+
+        match ((opcode & 0xF000) >> 12, x, y, n) {
+            (0x0, 0x0, 0xE, 0x0) => Self::ClearScreen,
+            (0x0, 0x0, 0xE, 0xE) => Self::Return,
+            (0x0, 0x0, 0xC, _) => Self::ScrollDown { n },
+            (0x0, 0x0, 0xD, _) => Self::ScrollUp { n },
+            (0x0, 0x0, 0xF, 0xB) => Self::ScrollRight,
+            (0x0, 0x0, 0xF, 0xC) => Self::ScrollLeft,
+            (0x0, 0x0, 0xF, 0xD) => Self::Exit,
+            (0x0, 0x0, 0xF, 0xE) => Self::Lores,
+            (0x0, 0x0, 0xF, 0xF) => Self::Hires,
+            (0x0, _, _, _) => Self::Sys { addr },
+            (0x1, _, _, _) => Self::Jump { addr },
+            (0x2, _, _, _) => Self::Call { addr },
+            (0x3, _, _, _) => Self::SkipEqualByte { x, byte },
+            (0x4, _, _, _) => Self::SkipNotEqualByte { x, byte },
+            (0x5, _, _, 0x0) => Self::SkipEqual { x, y },
+            (0x5, _, _, 0x2) => Self::SaveRange { x, y },
+            (0x5, _, _, 0x3) => Self::LoadRange { x, y },
+            (0x6, _, _, _) => Self::LoadByte { x, byte },
+            (0x7, _, _, _) => Self::AddByte { x, byte },
+            (0x8, _, _, 0x0) => Self::Move { x, y },
+            (0x8, _, _, 0x1) => Self::Or { x, y },
+            (0x8, _, _, 0x2) => Self::And { x, y },
+            (0x8, _, _, 0x3) => Self::Xor { x, y },
+            (0x8, _, _, 0x4) => Self::Add { x, y },
+            (0x8, _, _, 0x5) => Self::Sub { x, y },
+            (0x8, _, _, 0x6) => Self::ShiftRight { x, y },
+            (0x8, _, _, 0x7) => Self::SubN { x, y },
+            (0x8, _, _, 0xE) => Self::ShiftLeft { x, y },
+            (0x9, _, _, 0x0) => Self::SkipNotEqual { x, y },
+            (0xA, _, _, _) => Self::LoadIndex { addr },
+            (0xB, _, _, _) => Self::JumpOffset { addr },
+            (0xC, _, _, _) => Self::Random { x, byte },
+            (0xD, _, _, _) => Self::Draw { x, y, n },
+            (0xE, _, 0x9, 0xE) => Self::SkipKey { x },
+            (0xE, _, 0xA, 0x1) => Self::SkipNotKey { x },
+            (0xF, 0x0, 0x0, 0x0) => Self::LoadIndexLong,
+            (0xF, _, 0x0, 0x1) => Self::Plane { n: x },
+            (0xF, 0x0, 0x0, 0x2) => Self::Audio,
+            (0xF, _, 0x0, 0x7) => Self::LoadDelay { x },
+            (0xF, _, 0x0, 0xA) => Self::WaitKey { x },
+            (0xF, _, 0x1, 0x5) => Self::SetDelay { x },
+            (0xF, _, 0x1, 0x8) => Self::SetSound { x },
+            (0xF, _, 0x1, 0xE) => Self::AddIndex { x },
+            (0xF, _, 0x2, 0x9) => Self::Font { x },
+            (0xF, _, 0x3, 0x0) => Self::BigFont { x },
+            (0xF, _, 0x3, 0x3) => Self::Bcd { x },
+            (0xF, _, 0x3, 0xA) => Self::SetPitch { x },
+            (0xF, _, 0x5, 0x5) => Self::Store { x },
+            (0xF, _, 0x6, 0x5) => Self::Restore { x },
+            (0xF, _, 0x7, 0x5) => Self::SaveFlags { x },
+            (0xF, _, 0x8, 0x5) => Self::LoadFlags { x },
+            _ => Self::Unknown(opcode),
+        }
+    }
+
+    /// Returns the opcode of the instruction, the inverse of [`Instruction::decode`].
+    ///
+    /// Operands are masked to the width of their field. `F000` is returned without its address
+    /// word.
+    #[must_use]
+    pub const fn encode(self) -> u16 {
+        const fn xy(high: u16, x: u8, y: u8, low: u16) -> u16 {
+            high << 12 | ((x & 0xF) as u16) << 8 | ((y & 0xF) as u16) << 4 | low
+        }
+        const fn xkk(high: u16, x: u8, byte: u8) -> u16 {
+            high << 12 | ((x & 0xF) as u16) << 8 | byte as u16
+        }
+        const fn nnn(high: u16, addr: u16) -> u16 {
+            high << 12 | (addr & 0xFFF)
+        }
+
+        match self {
+            Self::Sys { addr } => nnn(0x0, addr),
+            Self::ClearScreen => 0x00E0,
+            Self::Return => 0x00EE,
+            Self::ScrollDown { n } => 0x00C0 | (n & 0xF) as u16,
+            Self::ScrollUp { n } => 0x00D0 | (n & 0xF) as u16,
+            Self::ScrollRight => 0x00FB,
+            Self::ScrollLeft => 0x00FC,
+            Self::Exit => 0x00FD,
+            Self::Lores => 0x00FE,
+            Self::Hires => 0x00FF,
+            Self::Jump { addr } => nnn(0x1, addr),
+            Self::Call { addr } => nnn(0x2, addr),
+            Self::SkipEqualByte { x, byte } => xkk(0x3, x, byte),
+            Self::SkipNotEqualByte { x, byte } => xkk(0x4, x, byte),
+            Self::SkipEqual { x, y } => xy(0x5, x, y, 0x0),
+            Self::SaveRange { x, y } => xy(0x5, x, y, 0x2),
+            Self::LoadRange { x, y } => xy(0x5, x, y, 0x3),
+            Self::LoadByte { x, byte } => xkk(0x6, x, byte),
+            Self::AddByte { x, byte } => xkk(0x7, x, byte),
+            Self::Move { x, y } => xy(0x8, x, y, 0x0),
+            Self::Or { x, y } => xy(0x8, x, y, 0x1),
+            Self::And { x, y } => xy(0x8, x, y, 0x2),
+            Self::Xor { x, y } => xy(0x8, x, y, 0x3),
+            Self::Add { x, y } => xy(0x8, x, y, 0x4),
+            Self::Sub { x, y } => xy(0x8, x, y, 0x5),
+            Self::ShiftRight { x, y } => xy(0x8, x, y, 0x6),
+            Self::SubN { x, y } => xy(0x8, x, y, 0x7),
+            Self::ShiftLeft { x, y } => xy(0x8, x, y, 0xE),
+            Self::SkipNotEqual { x, y } => xy(0x9, x, y, 0x0),
+            Self::LoadIndex { addr } => nnn(0xA, addr),
+            Self::JumpOffset { addr } => nnn(0xB, addr),
+            Self::Random { x, byte } => xkk(0xC, x, byte),
+            Self::Draw { x, y, n } => xy(0xD, x, y, (n & 0xF) as u16),
+            Self::SkipKey { x } => xkk(0xE, x, 0x9E),
+            Self::SkipNotKey { x } => xkk(0xE, x, 0xA1),
+            Self::LoadIndexLong => 0xF000,
+            Self::Plane { n } => xkk(0xF, n, 0x01),
+            Self::Audio => 0xF002,
+            Self::LoadDelay { x } => xkk(0xF, x, 0x07),
+            Self::WaitKey { x } => xkk(0xF, x, 0x0A),
+            Self::SetDelay { x } => xkk(0xF, x, 0x15),
+            Self::SetSound { x } => xkk(0xF, x, 0x18),
+            Self::AddIndex { x } => xkk(0xF, x, 0x1E),
+            Self::Font { x } => xkk(0xF, x, 0x29),
+            Self::BigFont { x } => xkk(0xF, x, 0x30),
+            Self::Bcd { x } => xkk(0xF, x, 0x33),
+            Self::SetPitch { x } => xkk(0xF, x, 0x3A),
+            Self::Store { x } => xkk(0xF, x, 0x55),
+            Self::Restore { x } => xkk(0xF, x, 0x65),
+            Self::SaveFlags { x } => xkk(0xF, x, 0x75),
+            Self::LoadFlags { x } => xkk(0xF, x, 0x85),
+            Self::Unknown(opcode) => opcode,
+        }
+    }
+
+    /// Returns the size of the instruction in bytes, including the address word of `F000 nnnn`.
+    #[must_use]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::LoadIndexLong => 4,
+            _ => 2,
+        }
+    }
+
+    /// Returns a [`fmt::Display`] implementation printing the mnemonic in the given syntax.
+    #[must_use]
+    pub const fn mnemonic(self, syntax: Syntax) -> Mnemonic {
+        Mnemonic {
+            instruction: self,
+            syntax,
+            long_addr: None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.mnemonic(Syntax::Cowgod).fmt(f)
+    }
+}
+
+/// Assembly syntax used when printing instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Syntax {
+    /// The mnemonics from Cowgod's Chip-8 Technical Reference, such as `LD V0, 0x12`.
+    #[default]
+    Cowgod,
+    /// The statements of the Octo assembler, such as `v0 := 0x12`.
+    Octo,
+}
+
+/// Prints an [`Instruction`] in a given [`Syntax`].
+#[derive(Debug, Clone, Copy)]
+pub struct Mnemonic {
+    instruction: Instruction,
+    syntax: Syntax,
+    // Address word following `F000`, when known
+    long_addr: Option<u16>,
+}
+
+impl Mnemonic {
+    pub(crate) const fn with_long_addr(mut self, addr: u16) -> Self {
+        self.long_addr = Some(addr);
+        self
+    }
+
+    fn fmt_cowgod(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.instruction {
+            Instruction::Sys { addr } => write!(f, "SYS 0x{addr:03X}"),
+            Instruction::ClearScreen => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::ScrollDown { n } => write!(f, "SCD {n}"),
+            Instruction::ScrollUp { n } => write!(f, "SCU {n}"),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::Lores => write!(f, "LOW"),
+            Instruction::Hires => write!(f, "HIGH"),
+            Instruction::Jump { addr } => write!(f, "JP 0x{addr:03X}"),
+            Instruction::Call { addr } => write!(f, "CALL 0x{addr:03X}"),
+            Instruction::SkipEqualByte { x, byte } => write!(f, "SE V{x:X}, 0x{byte:02X}"),
+            Instruction::SkipNotEqualByte { x, byte } => write!(f, "SNE V{x:X}, 0x{byte:02X}"),
+            Instruction::SkipEqual { x, y } => write!(f, "SE V{x:X}, V{y:X}"),
+            Instruction::SaveRange { x, y } => write!(f, "LD [I], V{x:X}-V{y:X}"),
+            Instruction::LoadRange { x, y } => write!(f, "LD V{x:X}-V{y:X}, [I]"),
+            Instruction::LoadByte { x, byte } => write!(f, "LD V{x:X}, 0x{byte:02X}"),
+            Instruction::AddByte { x, byte } => write!(f, "ADD V{x:X}, 0x{byte:02X}"),
+            Instruction::Move { x, y } => write!(f, "LD V{x:X}, V{y:X}"),
+            Instruction::Or { x, y } => write!(f, "OR V{x:X}, V{y:X}"),
+            Instruction::And { x, y } => write!(f, "AND V{x:X}, V{y:X}"),
+            Instruction::Xor { x, y } => write!(f, "XOR V{x:X}, V{y:X}"),
+            Instruction::Add { x, y } => write!(f, "ADD V{x:X}, V{y:X}"),
+            Instruction::Sub { x, y } => write!(f, "SUB V{x:X}, V{y:X}"),
+            Instruction::ShiftRight { x, y } => write!(f, "SHR V{x:X}, V{y:X}"),
+            Instruction::SubN { x, y } => write!(f, "SUBN V{x:X}, V{y:X}"),
+            Instruction::ShiftLeft { x, y } => write!(f, "SHL V{x:X}, V{y:X}"),
+            Instruction::SkipNotEqual { x, y } => write!(f, "SNE V{x:X}, V{y:X}"),
+            Instruction::LoadIndex { addr } => write!(f, "LD I, 0x{addr:03X}"),
+            Instruction::JumpOffset { addr } => write!(f, "JP V0, 0x{addr:03X}"),
+            Instruction::Random { x, byte } => write!(f, "RND V{x:X}, 0x{byte:02X}"),
+            Instruction::Draw { x, y, n } => write!(f, "DRW V{x:X}, V{y:X}, {n}"),
+            Instruction::SkipKey { x } => write!(f, "SKP V{x:X}"),
+            Instruction::SkipNotKey { x } => write!(f, "SKNP V{x:X}"),
+            Instruction::LoadIndexLong => match self.long_addr {
+                Some(addr) => write!(f, "LD I, long 0x{addr:04X}"),
+                None => write!(f, "LD I, long"),
+            },
+            Instruction::Plane { n } => write!(f, "PLANE {n}"),
+            Instruction::Audio => write!(f, "AUDIO"),
+            Instruction::LoadDelay { x } => write!(f, "LD V{x:X}, DT"),
+            Instruction::WaitKey { x } => write!(f, "LD V{x:X}, K"),
+            Instruction::SetDelay { x } => write!(f, "LD DT, V{x:X}"),
+            Instruction::SetSound { x } => write!(f, "LD ST, V{x:X}"),
+            Instruction::AddIndex { x } => write!(f, "ADD I, V{x:X}"),
+            Instruction::Font { x } => write!(f, "LD F, V{x:X}"),
+            Instruction::BigFont { x } => write!(f, "LD HF, V{x:X}"),
+            Instruction::Bcd { x } => write!(f, "LD B, V{x:X}"),
+            Instruction::SetPitch { x } => write!(f, "LD PITCH, V{x:X}"),
+            Instruction::Store { x } => write!(f, "LD [I], V{x:X}"),
+            Instruction::Restore { x } => write!(f, "LD V{x:X}, [I]"),
+            Instruction::SaveFlags { x } => write!(f, "LD R, V{x:X}"),
+            Instruction::LoadFlags { x } => write!(f, "LD V{x:X}, R"),
+            Instruction::Unknown(opcode) => write!(f, "DW 0x{opcode:04X}"),
+        }
+    }
+
+    fn fmt_octo(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.instruction {
+            // Octo has no statement for machine code calls, so they are emitted as raw bytes
+            Instruction::Sys { addr } => write!(f, "0x{:02X} 0x{:02X}", addr >> 8, addr & 0xFF),
+            Instruction::ClearScreen => write!(f, "clear"),
+            Instruction::Return => write!(f, "return"),
+            Instruction::ScrollDown { n } => write!(f, "scroll-down {n}"),
+            Instruction::ScrollUp { n } => write!(f, "scroll-up {n}"),
+            Instruction::ScrollRight => write!(f, "scroll-right"),
+            Instruction::ScrollLeft => write!(f, "scroll-left"),
+            Instruction::Exit => write!(f, "exit"),
+            Instruction::Lores => write!(f, "lores"),
+            Instruction::Hires => write!(f, "hires"),
+            Instruction::Jump { addr } => write!(f, "jump 0x{addr:03X}"),
+            Instruction::Call { addr } => write!(f, ":call 0x{addr:03X}"),
+            // Octo conditions describe when the following statement runs, the opposite of a skip
+            Instruction::SkipEqualByte { x, byte } => write!(f, "if v{x:x} != 0x{byte:02X} then"),
+            Instruction::SkipNotEqualByte { x, byte } => {
+                write!(f, "if v{x:x} == 0x{byte:02X} then")
+            }
+            Instruction::SkipEqual { x, y } => write!(f, "if v{x:x} != v{y:x} then"),
+            Instruction::SaveRange { x, y } => write!(f, "save v{x:x} - v{y:x}"),
+            Instruction::LoadRange { x, y } => write!(f, "load v{x:x} - v{y:x}"),
+            Instruction::LoadByte { x, byte } => write!(f, "v{x:x} := 0x{byte:02X}"),
+            Instruction::AddByte { x, byte } => write!(f, "v{x:x} += 0x{byte:02X}"),
+            Instruction::Move { x, y } => write!(f, "v{x:x} := v{y:x}"),
+            Instruction::Or { x, y } => write!(f, "v{x:x} |= v{y:x}"),
+            Instruction::And { x, y } => write!(f, "v{x:x} &= v{y:x}"),
+            Instruction::Xor { x, y } => write!(f, "v{x:x} ^= v{y:x}"),
+            Instruction::Add { x, y } => write!(f, "v{x:x} += v{y:x}"),
+            Instruction::Sub { x, y } => write!(f, "v{x:x} -= v{y:x}"),
+            Instruction::ShiftRight { x, y } => write!(f, "v{x:x} >>= v{y:x}"),
+            Instruction::SubN { x, y } => write!(f, "v{x:x} =- v{y:x}"),
+            Instruction::ShiftLeft { x, y } => write!(f, "v{x:x} <<= v{y:x}"),
+            Instruction::SkipNotEqual { x, y } => write!(f, "if v{x:x} == v{y:x} then"),
+            Instruction::LoadIndex { addr } => write!(f, "i := 0x{addr:03X}"),
+            Instruction::JumpOffset { addr } => write!(f, "jump0 0x{addr:03X}"),
+            Instruction::Random { x, byte } => write!(f, "v{x:x} := random 0x{byte:02X}"),
+            Instruction::Draw { x, y, n } => write!(f, "sprite v{x:x} v{y:x} {n}"),
+            Instruction::SkipKey { x } => write!(f, "if v{x:x} -key then"),
+            Instruction::SkipNotKey { x } => write!(f, "if v{x:x} key then"),
+            Instruction::LoadIndexLong => match self.long_addr {
+                Some(addr) => write!(f, "i := long 0x{addr:04X}"),
+                None => write!(f, "i := long"),
+            },
+            Instruction::Plane { n } => write!(f, "plane {n}"),
+            Instruction::Audio => write!(f, "audio"),
+            Instruction::LoadDelay { x } => write!(f, "v{x:x} := delay"),
+            Instruction::WaitKey { x } => write!(f, "v{x:x} := key"),
+            Instruction::SetDelay { x } => write!(f, "delay := v{x:x}"),
+            Instruction::SetSound { x } => write!(f, "buzzer := v{x:x}"),
+            Instruction::AddIndex { x } => write!(f, "i += v{x:x}"),
+            Instruction::Font { x } => write!(f, "i := hex v{x:x}"),
+            Instruction::BigFont { x } => write!(f, "i := bighex v{x:x}"),
+            Instruction::Bcd { x } => write!(f, "bcd v{x:x}"),
+            Instruction::SetPitch { x } => write!(f, "pitch := v{x:x}"),
+            Instruction::Store { x } => write!(f, "save v{x:x}"),
+            Instruction::Restore { x } => write!(f, "load v{x:x}"),
+            Instruction::SaveFlags { x } => write!(f, "saveflags v{x:x}"),
+            Instruction::LoadFlags { x } => write!(f, "loadflags v{x:x}"),
+            Instruction::Unknown(opcode) => {
+                write!(f, "0x{:02X} 0x{:02X}", opcode >> 8, opcode & 0xFF)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.syntax {
+            Syntax::Cowgod => self.fmt_cowgod(f),
+            Syntax::Octo => self.fmt_octo(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_round_trip() {
+        for opcode in 0..=u16::MAX {
+            assert_eq!(Instruction::decode(opcode).encode(), opcode, "{opcode:04X}");
+        }
+    }
+
+    #[test]
+    fn mnemonics() {
+        let cases = [
+            (0x00E0, "CLS", "clear"),
+            (0x00C4, "SCD 4", "scroll-down 4"),
+            (0x1234, "JP 0x234", "jump 0x234"),
+            (0x8126, "SHR V1, V2", "v1 >>= v2"),
+            (0xD125, "DRW V1, V2, 5", "sprite v1 v2 5"),
+            (0xF265, "LD V2, [I]", "load v2"),
+        ];
+        for (opcode, cowgod, octo) in cases {
+            let instruction = Instruction::decode(opcode);
+            assert_eq!(instruction.mnemonic(Syntax::Cowgod).to_string(), cowgod);
+            assert_eq!(instruction.mnemonic(Syntax::Octo).to_string(), octo);
+        }
+        assert_eq!(Instruction::decode(0x5121), Instruction::Unknown(0x5121));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::flow::{self, Step};
+use crate::{Instruction, START_ADDR, Syntax};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+// Bytes per line of data that isn't drawn as a sprite
+const DATA_ROW: usize = 8;
+
+/// Decompiles a ROM loaded at address `0x200` into Octo source that assembles back to the
+/// same bytes.
+///
+/// Code is told apart from data by following jumps, calls, skips and `Bnnn` jump tables from
+/// `0x200`, so bytes that are only reached in ways that can't be followed, such as
+/// self-modifying code, come out as data. Jump targets get labels: `main` for the entry
+/// point, `sub_xxx` for subroutines, `label_xxx` for other code and `data_xxx` for data. Data
+/// pointed to by `i :=` is printed as binary literals, one byte per line, so sprites can be
+/// read as pictures.
+#[must_use]
+pub fn decompile(rom: &[u8]) -> String {
+    let steps = flow::explore(rom);
+    let items = layout(rom, &steps);
+    let labels = labels(&items);
+    let sprites: BTreeSet<u16> = items
+        .iter()
+        .filter_map(|item| match item.kind {
+            ItemKind::Code(step) => match step.instruction {
+                Instruction::LoadIndex { addr } => Some(addr),
+                _ => step.long_addr,
+            },
+            ItemKind::Data(_) => None,
+        })
+        .collect();
+
+    let mut source = String::new();
+    let mut data = Vec::new();
+    let mut is_sprite = false;
+
+    for item in &items {
+        let label = labels.get(&item.addr);
+        if label.is_some() || matches!(item.kind, ItemKind::Code(_)) {
+            write_data(&mut source, &data, is_sprite);
+            data.clear();
+        }
+        if let Some(label) = label {
+            if !source.is_empty() {
+                source.push('\n');
+            }
+            let _ = writeln!(source, ": {label}");
+            is_sprite = sprites.contains(&item.addr);
+        }
+
+        match item.kind {
+            ItemKind::Code(step) => {
+                is_sprite = false;
+                // The statement guarded by a skip goes on the line of its `if ... then`
+                if label.is_none() && source.ends_with(" then\n") {
+                    source.pop();
+                    let _ = writeln!(source, " {}", statement(step, &labels));
+                } else {
+                    let _ = writeln!(source, "  {}", statement(step, &labels));
+                }
+            }
+            ItemKind::Data(byte) => data.push(byte),
+        }
+    }
+    write_data(&mut source, &data, is_sprite);
+
+    source
+}
+
+/// A piece of the ROM printed as one statement, or one byte of data.
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    addr: u16,
+    kind: ItemKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ItemKind {
+    Code(Step),
+    Data(u8),
+}
+
+/// Splits the ROM into instructions and data bytes. Instructions reached in the middle of
+/// another one are left out, since the source can only place each byte once.
+fn layout(rom: &[u8], steps: &BTreeMap<u16, Step>) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut offset = 0;
+
+    while offset < rom.len() {
+        let addr = (START_ADDR + offset) as u16;
+        match steps.get(&addr) {
+            Some(&step) => {
+                items.push(Item {
+                    addr,
+                    kind: ItemKind::Code(step),
+                });
+                offset += step.size() as usize;
+            }
+            None => {
+                items.push(Item {
+                    addr,
+                    kind: ItemKind::Data(rom[offset]),
+                });
+                offset += 1;
+            }
+        }
+    }
+
+    items
+}
+
+/// Names the addresses referenced by the code. Addresses that don't start an item can't be
+/// labelled and are printed as numbers.
+fn labels(items: &[Item]) -> BTreeMap<u16, String> {
+    let starts: BTreeMap<u16, bool> = items
+        .iter()
+        .map(|item| (item.addr, matches!(item.kind, ItemKind::Code(_))))
+        .collect();
+
+    let mut labels = BTreeMap::new();
+    if !items.is_empty() {
+        labels.insert(START_ADDR as u16, String::from("main"));
+    }
+
+    // Subroutines are named first, so a subroutine that is also jumped to keeps its name
+    let mut references: Vec<(u16, &str)> = items
+        .iter()
+        .filter_map(|item| match item.kind {
+            ItemKind::Code(step) => reference(step),
+            ItemKind::Data(_) => None,
+        })
+        .collect();
+    references.sort_by_key(|&(_, prefix)| prefix != "sub");
+
+    for (addr, prefix) in references {
+        let Some(&is_code) = starts.get(&addr) else {
+            continue;
+        };
+        let prefix = match prefix {
+            "sub" => "sub",
+            _ if is_code => "label",
+            _ => "data",
+        };
+        labels
+            .entry(addr)
+            .or_insert_with(|| format!("{prefix}_{addr:03x}"));
+    }
+
+    labels
+}
+
+/// Returns the address an instruction refers to, with the prefix of its label.
+fn reference(step: Step) -> Option<(u16, &'static str)> {
+    match step.instruction {
+        Instruction::Call { addr } => Some((addr, "sub")),
+        Instruction::Jump { addr } | Instruction::JumpOffset { addr } => Some((addr, "label")),
+        Instruction::LoadIndex { addr } => Some((addr, "data")),
+        Instruction::LoadIndexLong => step.long_addr.map(|addr| (addr, "data")),
+        _ => None,
+    }
+}
+
+fn statement(step: Step, labels: &BTreeMap<u16, String>) -> String {
+    let label = reference(step).and_then(|(addr, _)| labels.get(&addr));
+
+    match (step.instruction, label) {
+        (Instruction::Call { .. }, Some(label)) => label.clone(),
+        (Instruction::Jump { .. }, Some(label)) => format!("jump {label}"),
+        (Instruction::JumpOffset { .. }, Some(label)) => format!("jump0 {label}"),
+        (Instruction::LoadIndex { .. }, Some(label)) => format!("i := {label}"),
+        (Instruction::LoadIndexLong, Some(label)) => format!("i := long {label}"),
+        (instruction, _) => {
+            let mnemonic = instruction.mnemonic(Syntax::Octo);
+            match step.long_addr {
+                Some(addr) => mnemonic.with_long_addr(addr).to_string(),
+                None => mnemonic.to_string(),
+            }
+        }
+    }
+}
+
+fn write_data(source: &mut String, data: &[u8], is_sprite: bool) {
+    if is_sprite {
+        for byte in data {
+            let _ = writeln!(source, "  0b{byte:08b}");
+        }
+        return;
+    }
+
+    for row in data.chunks(DATA_ROW) {
+        let row: Vec<String> = row.iter().map(|byte| format!("0x{byte:02X}")).collect();
+        let _ = writeln!(source, "  {}", row.join(" "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble;
+
+    fn round_trip(rom: &[u8]) -> String {
+        let source = decompile(rom);
+        let program = assemble(&source).unwrap_or_else(|err| panic!("{err}\n{source}"));
+        assert_eq!(program.rom, rom, "{source}");
+        source
+    }
+
+    #[test]
+    fn odd_jump_target() {
+        // Jumps over a data byte to code at an odd address
+        let source = round_trip(&[0x12, 0x03, 0xAA, 0x60, 0x01, 0x12, 0x05]);
+        assert!(source.contains("jump label_203"));
+        assert!(source.contains("0xAA"));
+    }
+
+    #[test]
+    fn skip_over_long_index() {
+        // A skip over the 4-byte F000 lands after its address word
+        let source = round_trip(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01, 0x12, 0x08]);
+        assert!(source.contains("if v0 != 0x00 then i := long 0x1234\n  v0 := 0x01"));
+    }
+
+    #[test]
+    fn subroutine_and_sprite() {
+        let source = round_trip(&[
+            0x22, 0x06, 0xD0, 0x11, 0x12, 0x04, 0xA2, 0x0A, 0x00, 0xEE, 0x81,
+        ]);
+        assert!(source.contains(": sub_206"));
+        assert!(source.contains("0b10000001"));
+    }
+}
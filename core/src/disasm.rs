@@ -0,0 +1,185 @@
+use crate::{Instruction, START_ADDR, Syntax};
+use std::fmt;
+use std::ops::Range;
+
+/// One entry of a disassembly listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub addr: u16,
+    pub kind: LineKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Code {
+        opcode: u16,
+        instruction: Instruction,
+        /// The address word following `F000`.
+        long_addr: Option<u16>,
+    },
+    Data(u8),
+}
+
+impl Line {
+    /// Returns a [`fmt::Display`] implementation printing the address, the raw bytes and the
+    /// mnemonic in the given syntax.
+    #[must_use]
+    pub const fn listing(&self, syntax: Syntax) -> Listing {
+        Listing {
+            line: *self,
+            syntax,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.listing(Syntax::Cowgod).fmt(f)
+    }
+}
+
+/// Prints a [`Line`] in a given [`Syntax`].
+#[derive(Debug, Clone, Copy)]
+pub struct Listing {
+    line: Line,
+    syntax: Syntax,
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (bytes, text) = match self.line.kind {
+            LineKind::Code {
+                opcode,
+                instruction,
+                long_addr,
+            } => {
+                let mut mnemonic = instruction.mnemonic(self.syntax);
+                let bytes = if let Some(addr) = long_addr {
+                    mnemonic = mnemonic.with_long_addr(addr);
+                    format!("{opcode:04X} {addr:04X}")
+                } else {
+                    format!("{opcode:04X}")
+                };
+                (bytes, mnemonic.to_string())
+            }
+            LineKind::Data(byte) => {
+                let text = match self.syntax {
+                    Syntax::Cowgod => format!("DB 0x{byte:02X}"),
+                    Syntax::Octo => format!("0x{byte:02X}"),
+                };
+                (format!("{byte:02X}"), text)
+            }
+        };
+        write!(f, "0x{:03X}  {bytes:<9}  {text}", self.line.addr)
+    }
+}
+
+/// Disassembles a ROM loaded at address `0x200` into a listing.
+///
+/// Bytes inside any of the `data` address ranges are listed as data, as are instructions that
+/// would overlap such a range and a trailing odd byte.
+#[must_use]
+pub fn disassemble(rom: &[u8], data: &[Range<u16>]) -> Vec<Line> {
+    let is_data = |offset: usize| {
+        let addr = START_ADDR + offset;
+        offset >= rom.len()
+            || data
+                .iter()
+                .any(|range| (range.start as usize..range.end as usize).contains(&addr))
+    };
+    let word = |offset: usize| u16::from_be_bytes([rom[offset], rom[offset + 1]]);
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    while offset < rom.len() {
+        let addr = (START_ADDR + offset) as u16;
+
+        if is_data(offset) || is_data(offset + 1) {
+            lines.push(Line {
+                addr,
+                kind: LineKind::Data(rom[offset]),
+            });
+            offset += 1;
+            continue;
+        }
+
+        let opcode = word(offset);
+        let instruction = Instruction::decode(opcode);
+        let long_addr = if instruction == Instruction::LoadIndexLong
+            && !is_data(offset + 2)
+            && !is_data(offset + 3)
+        {
+            Some(word(offset + 2))
+        } else {
+            None
+        };
+
+        lines.push(Line {
+            addr,
+            kind: LineKind::Code {
+                opcode,
+                instruction,
+                long_addr,
+            },
+        });
+        offset += if long_addr.is_some() { 4 } else { 2 };
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(lines: &[Line]) -> Vec<(u16, Option<u16>)> {
+        lines
+            .iter()
+            .map(|line| match line.kind {
+                LineKind::Code { opcode, .. } => (line.addr, Some(opcode)),
+                LineKind::Data(_) => (line.addr, None),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn data_ranges() {
+        let rom = [
+            0x00, 0xE0, 0xF0, 0x00, 0x12, 0x34, 0xAB, 0xCD, 0x12, 0x00, 0xFF,
+        ];
+
+        let lines = disassemble(&rom, &[]);
+        assert_eq!(
+            kinds(&lines),
+            [
+                (0x200, Some(0x00E0)),
+                (0x202, Some(0xF000)),
+                (0x206, Some(0xABCD)),
+                (0x208, Some(0x1200)),
+                (0x20A, None),
+            ]
+        );
+        assert_eq!(
+            lines[1].listing(Syntax::Octo).to_string(),
+            "0x202  F000 1234  i := long 0x1234"
+        );
+        assert_eq!(lines[4].to_string(), "0x20A  FF         DB 0xFF");
+
+        // Instructions overlapping a range become data, and F000 is left without its address
+        let lines = disassemble(&rom, &[0x205..0x208, 0x20A..0x20B]);
+        assert_eq!(
+            kinds(&lines),
+            [
+                (0x200, Some(0x00E0)),
+                (0x202, Some(0xF000)),
+                (0x204, None),
+                (0x205, None),
+                (0x206, None),
+                (0x207, None),
+                (0x208, Some(0x1200)),
+                (0x20A, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Instruction, START_ADDR};
+use std::collections::BTreeMap;
+
+/// An instruction reached by following the control flow of a ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Step {
+    pub opcode: u16,
+    pub instruction: Instruction,
+    /// The address word following `F000`.
+    pub long_addr: Option<u16>,
+}
+
+/// How control moves from an instruction to one of its successors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// To the following instruction, including the return from a call.
+    Next,
+    /// Over the following instruction, when a skip is taken.
+    Skip,
+    Jump,
+    Call,
+    /// To an entry of a `Bnnn` jump table.
+    Computed,
+}
+
+impl Step {
+    /// Decodes the instruction at `addr`, or returns `None` when it doesn't fit in the ROM or
+    /// isn't a valid instruction.
+    pub fn fetch(rom: &[u8], addr: u16) -> Option<Self> {
+        let opcode = word(rom, addr)?;
+        let instruction = Instruction::decode(opcode);
+        let long_addr = match instruction {
+            Instruction::Sys { .. } | Instruction::Unknown(_) => return None,
+            Instruction::LoadIndexLong => Some(word(rom, addr.wrapping_add(2))?),
+            _ => None,
+        };
+
+        Some(Self {
+            opcode,
+            instruction,
+            long_addr,
+        })
+    }
+
+    pub fn size(&self) -> u16 {
+        self.instruction.size() as u16
+    }
+
+    /// Returns where control can go after executing the instruction at `addr`.
+    ///
+    /// The target of `Bnnn` depends on `V0`, so it is assumed to be a table of jumps starting
+    /// at `nnn`, which is how the instruction is normally used.
+    pub fn successors(&self, rom: &[u8], addr: u16) -> Vec<(u16, EdgeKind)> {
+        let next = addr.wrapping_add(self.size());
+
+        match self.instruction {
+            Instruction::Return | Instruction::Exit => Vec::new(),
+            Instruction::Jump { addr } => vec![(addr, EdgeKind::Jump)],
+            Instruction::Call { addr } => vec![(addr, EdgeKind::Call), (next, EdgeKind::Next)],
+            Instruction::JumpOffset { addr } => {
+                let is_jump = |addr| {
+                    word(rom, addr).is_some_and(|opcode| {
+                        matches!(Instruction::decode(opcode), Instruction::Jump { .. })
+                    })
+                };
+                let mut entries = vec![(addr, EdgeKind::Computed)];
+                let mut entry = addr;
+                while is_jump(entry) && is_jump(entry.wrapping_add(2)) {
+                    entry = entry.wrapping_add(2);
+                    entries.push((entry, EdgeKind::Computed));
+                }
+                entries
+            }
+            Instruction::SkipEqualByte { .. }
+            | Instruction::SkipNotEqualByte { .. }
+            | Instruction::SkipEqual { .. }
+            | Instruction::SkipNotEqual { .. }
+            | Instruction::SkipKey { .. }
+            | Instruction::SkipNotKey { .. } => {
+                // Like the interpreter, skips step over both words of `F000 nnnn`
+                let skipped = if word(rom, next) == Some(0xF000) {
+                    4
+                } else {
+                    2
+                };
+                vec![
+                    (next, EdgeKind::Next),
+                    (next.wrapping_add(skipped), EdgeKind::Skip),
+                ]
+            }
+            _ => vec![(next, EdgeKind::Next)],
+        }
+    }
+}
+
+/// Follows every path from `0x200` and returns the instructions found, by address.
+///
+/// Paths stop at returns, at `00FD` and at anything that isn't a valid instruction or falls
+/// outside of the ROM.
+pub(crate) fn explore(rom: &[u8]) -> BTreeMap<u16, Step> {
+    let mut steps = BTreeMap::new();
+    let mut pending = vec![START_ADDR as u16];
+
+    while let Some(addr) = pending.pop() {
+        if steps.contains_key(&addr) {
+            continue;
+        }
+        let Some(step) = Step::fetch(rom, addr) else {
+            continue;
+        };
+        steps.insert(addr, step);
+        pending.extend(step.successors(rom, addr).into_iter().map(|(to, _)| to));
+    }
+
+    steps
+}
+
+fn word(rom: &[u8], addr: u16) -> Option<u16> {
+    let offset = (addr as usize).checked_sub(START_ADDR)?;
+    let bytes = rom.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
@@ -0,0 +1,150 @@
+use crate::{Chip8, KEY_COUNT, REGISTER_COUNT, STACK_SIZE};
+
+/// A copy of the CPU registers, taken by [`Chip8::registers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    /// The general purpose registers `V0` to `VF`.
+    pub v: [u8; REGISTER_COUNT],
+    pub index: u16,
+    pub pc: u16,
+    /// The number of return addresses on the stack.
+    pub sp: u8,
+    pub stack: [u16; STACK_SIZE],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+impl Registers {
+    /// Returns the return addresses currently on the stack, oldest first.
+    #[must_use]
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack[..(self.sp as usize).min(STACK_SIZE)]
+    }
+}
+
+impl Chip8 {
+    #[must_use]
+    pub const fn registers(&self) -> Registers {
+        Registers {
+            v: self.registers,
+            index: self.index,
+            pc: self.pc,
+            sp: self.sp,
+            stack: self.stack,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        }
+    }
+
+    /// Returns the value of register `Vx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is not a register index below 16.
+    #[must_use]
+    pub const fn register(&self, x: usize) -> u8 {
+        self.registers[x]
+    }
+
+    #[must_use]
+    pub const fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Returns the return addresses currently on the stack, oldest first.
+    #[must_use]
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp as usize]
+    }
+
+    #[must_use]
+    pub const fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    #[must_use]
+    pub const fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    #[must_use]
+    pub const fn keys(&self) -> &[bool; KEY_COUNT] {
+        &self.keys
+    }
+
+    /// Returns the whole address space, including the fonts and the loaded ROM.
+    #[must_use]
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Returns the address space for editing. Writes don't trigger watchpoints.
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Sets register `Vx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` is not a register index below 16.
+    pub const fn set_register(&mut self, x: usize, value: u8) {
+        self.registers[x] = value;
+    }
+
+    pub const fn set_index(&mut self, index: u16) {
+        self.index = index;
+    }
+
+    pub const fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub const fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    pub const fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers() {
+        let mut chip8 = Chip8::new();
+        chip8.load(&[0x60, 0x42, 0xA1, 0x23, 0x22, 0x08]).unwrap();
+        for _ in 0..3 {
+            chip8.emulate().unwrap();
+        }
+
+        let registers = chip8.registers();
+        assert_eq!(registers.v[0], 0x42);
+        assert_eq!(registers.index, 0x123);
+        assert_eq!(registers.pc, 0x208);
+        assert_eq!(registers.call_stack(), [0x206]);
+        assert_eq!(chip8.stack(), [0x206]);
+    }
+
+    #[test]
+    fn setters() {
+        let mut chip8 = Chip8::new();
+        chip8.set_register(0xF, 1);
+        chip8.set_index(0x300);
+        chip8.set_pc(0x204);
+        chip8.set_delay_timer(5);
+        chip8.set_sound_timer(6);
+        chip8.memory_mut()[0x300] = 0xAB;
+
+        let registers = chip8.registers();
+        assert_eq!(registers.v[0xF], 1);
+        assert_eq!(chip8.index(), 0x300);
+        assert_eq!(chip8.pc(), 0x204);
+        assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (5, 6));
+        assert_eq!(chip8.memory()[0x300], 0xAB);
+        assert!(registers.call_stack().is_empty());
+    }
+}
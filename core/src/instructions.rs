@@ -1,22 +1,64 @@
-use crate::{Chip8, ExecuteError, KEY_COUNT, STACK_SIZE, VIDEO_HEIGHT, VIDEO_WIDTH};
-use rand::RngExt;
+use crate::{AUDIO_PATTERN_SIZE, Access, BIG_FONT_ADDR, Chip8, ErrorKind, KEY_COUNT, STACK_SIZE};
 
 impl Chip8 {
     // 00E0: CLS
     pub(crate) fn op_00e0(&mut self) {
-        self.framebuffer.fill(false);
+        for pixel in &mut self.framebuffer {
+            *pixel &= !self.planes;
+        }
     }
 
     //00EE: RET
-    pub(crate) const fn op_00ee(&mut self) -> Result<(), ExecuteError> {
+    pub(crate) const fn op_00ee(&mut self) -> Result<(), ErrorKind> {
         if self.sp == 0 {
-            return Err(ExecuteError::StackUnderflow);
+            return Err(ErrorKind::StackUnderflow);
         }
         self.sp -= 1;
         self.pc = self.stack[self.sp as usize];
         Ok(())
     }
 
+    // 00Cn: SCD nibble
+    pub(crate) fn op_00cn(&mut self, opcode: u16) {
+        let rows = (opcode & 0x000F) as isize;
+
+        self.scroll(0, rows);
+    }
+
+    // 00Dn: SCU nibble
+    pub(crate) fn op_00dn(&mut self, opcode: u16) {
+        let rows = (opcode & 0x000F) as isize;
+
+        self.scroll(0, -rows);
+    }
+
+    // 00FB: SCR
+    pub(crate) fn op_00fb(&mut self) {
+        self.scroll(4, 0);
+    }
+
+    // 00FC: SCL
+    pub(crate) fn op_00fc(&mut self) {
+        self.scroll(-4, 0);
+    }
+
+    // 00FD: EXIT
+    pub(crate) const fn op_00fd(&mut self) {
+        self.halted = true;
+    }
+
+    // 00FE: LOW
+    pub(crate) fn op_00fe(&mut self) {
+        self.hires = false;
+        self.framebuffer.fill(0);
+    }
+
+    // 00FF: HIGH
+    pub(crate) fn op_00ff(&mut self) {
+        self.hires = true;
+        self.framebuffer.fill(0);
+    }
+
     // 1nnn: JP addr
     pub(crate) const fn op_1nnn(&mut self, opcode: u16) {
         let addr = opcode & 0x0FFF;
@@ -24,9 +66,9 @@ impl Chip8 {
     }
 
     // 2nnn: CALL addr
-    pub(crate) const fn op_2nnn(&mut self, opcode: u16) -> Result<(), ExecuteError> {
+    pub(crate) const fn op_2nnn(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         if self.sp as usize >= STACK_SIZE {
-            return Err(ExecuteError::StackOverflow);
+            return Err(ErrorKind::StackOverflow);
         }
         let addr = opcode & 0x0FFF;
         self.stack[self.sp as usize] = self.pc;
@@ -41,7 +83,7 @@ impl Chip8 {
         let byte = (opcode & 0x00FF) as u8;
 
         if self.registers[vx] == byte {
-            self.pc += 2;
+            self.skip();
         }
     }
 
@@ -51,7 +93,7 @@ impl Chip8 {
         let byte = (opcode & 0x00FF) as u8;
 
         if self.registers[vx] != byte {
-            self.pc += 2;
+            self.skip();
         }
     }
 
@@ -61,8 +103,32 @@ impl Chip8 {
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
         if self.registers[vx] == self.registers[vy] {
-            self.pc += 2;
+            self.skip();
+        }
+    }
+
+    // 5xy2: LD [I], Vx-Vy
+    pub(crate) fn op_5xy2(&mut self, opcode: u16) -> Result<(), ErrorKind> {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+        let vy = ((opcode & 0x00F0) >> 4) as usize;
+
+        for i in 0..=vx.abs_diff(vy) {
+            let register = if vx <= vy { vx + i } else { vx - i };
+            self.write_memory(self.index as usize + i, self.registers[register])?;
+        }
+        Ok(())
+    }
+
+    // 5xy3: LD Vx-Vy, [I]
+    pub(crate) fn op_5xy3(&mut self, opcode: u16) -> Result<(), ErrorKind> {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+        let vy = ((opcode & 0x00F0) >> 4) as usize;
+
+        for i in 0..=vx.abs_diff(vy) {
+            let register = if vx <= vy { vx + i } else { vx - i };
+            self.registers[register] = self.read_memory(self.index as usize + i)?;
         }
+        Ok(())
     }
 
     // 6xkk: LD Vx, byte
@@ -94,7 +160,7 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
-        if self.quirks.vf_reset {
+        if self.quirks.vf_reset() {
             self.registers[0xF] = 0;
         }
 
@@ -106,7 +172,7 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
-        if self.quirks.vf_reset {
+        if self.quirks.vf_reset() {
             self.registers[0xF] = 0;
         }
 
@@ -118,7 +184,7 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
-        if self.quirks.vf_reset {
+        if self.quirks.vf_reset() {
             self.registers[0xF] = 0;
         }
 
@@ -152,7 +218,7 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
-        if !self.quirks.shifting {
+        if !self.quirks.shifting() {
             self.registers[vx] = self.registers[vy];
         }
 
@@ -178,7 +244,7 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
-        if !self.quirks.shifting {
+        if !self.quirks.shifting() {
             self.registers[vx] = self.registers[vy];
         }
 
@@ -194,7 +260,7 @@ impl Chip8 {
         let vy = ((opcode & 0x00F0) >> 4) as usize;
 
         if self.registers[vx] != self.registers[vy] {
-            self.pc += 2;
+            self.skip();
         }
     }
 
@@ -207,7 +273,7 @@ impl Chip8 {
     // Bnnn: JP V0, addr
     pub(crate) const fn op_bnnn(&mut self, opcode: u16) {
         let addr = opcode & 0x0FFF;
-        if self.quirks.jumping {
+        if self.quirks.jumping() {
             let vx = ((opcode & 0x0F00) >> 8) as usize;
             self.pc = addr + self.registers[vx] as u16;
         } else {
@@ -220,67 +286,133 @@ impl Chip8 {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let byte = (opcode & 0x00FF) as u8;
 
-        let rand_byte: u8 = rand::rng().random();
+        let rand_byte = self.rng.next_byte();
 
         self.registers[vx] = rand_byte & byte;
     }
 
     // Dxyn: DRW Vx, Vy, nibble
-    pub(crate) fn op_dxyn(&mut self, opcode: u16) {
+    // Dxy0: DRW Vx, Vy, 0 draws a 16x16 sprite, 8x16 in lores or nothing depending on the quirks
+    pub(crate) fn op_dxyn(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let vy = ((opcode & 0x00F0) >> 4) as usize;
-        let height = (opcode & 0x000F) as u8;
+        let nibble = (opcode & 0x000F) as usize;
 
-        let x_pos = self.registers[vx] % VIDEO_WIDTH as u8;
-        let y_pos = self.registers[vy] % VIDEO_HEIGHT as u8;
+        let (sprite_width, sprite_height) = match nibble {
+            0 if !self.quirks.large_sprites() => (8, 0),
+            0 if self.hires || self.quirks.wide_lores_sprites() => (16, 16),
+            0 => (8, 16),
+            _ => (8, nibble),
+        };
+        let bytes_per_row = sprite_width / 8;
 
-        let mut flipped = false;
+        let width = self.width();
+        let height = self.height();
+
+        let x_pos = self.registers[vx] as usize % width;
+        let y_pos = self.registers[vy] as usize % height;
 
-        for row in 0..height {
-            let sprite_byte = self.memory[(self.index + row as u16) as usize];
+        let mut flipped = false;
+        let mut addr = self.index as usize;
 
-            if self.quirks.clipping && (y_pos + row) as usize >= VIDEO_HEIGHT {
-                break;
+        // Each selected plane takes the next sprite from memory
+        for plane in (0..4).map(|bit| 1 << bit) {
+            if self.planes & plane == 0 {
+                continue;
             }
 
-            for col in 0..8 {
-                if self.quirks.clipping && (x_pos + col) as usize >= VIDEO_WIDTH {
+            for row in 0..sprite_height {
+                let row_addr = addr + row * bytes_per_row;
+
+                if self.quirks.clipping() && y_pos + row >= height {
                     break;
                 }
 
-                if (sprite_byte & (0x80 >> col)) != 0 {
-                    let wrapped_x_pos = (x_pos + col) as usize % VIDEO_WIDTH;
-                    let wrapped_y_pos = (y_pos + row) as usize % VIDEO_HEIGHT;
-                    let idx = wrapped_x_pos + VIDEO_WIDTH * wrapped_y_pos;
+                let sprite_row = if bytes_per_row == 2 {
+                    u16::from_be_bytes([
+                        self.read_memory(row_addr)?,
+                        self.read_memory(row_addr + 1)?,
+                    ])
+                } else {
+                    (self.read_memory(row_addr)? as u16) << 8
+                };
+
+                for col in 0..sprite_width {
+                    if self.quirks.clipping() && x_pos + col >= width {
+                        break;
+                    }
+
+                    if (sprite_row & (0x8000 >> col)) != 0 {
+                        let wrapped_x_pos = (x_pos + col) % width;
+                        let wrapped_y_pos = (y_pos + row) % height;
+                        let idx = wrapped_x_pos + width * wrapped_y_pos;
 
-                    flipped |= self.framebuffer[idx];
-                    self.framebuffer[idx] ^= true;
+                        flipped |= self.framebuffer[idx] & plane != 0;
+                        self.framebuffer[idx] ^= plane;
+                    }
                 }
             }
+
+            addr += sprite_height * bytes_per_row;
         }
         self.registers[0xF] = flipped as u8;
+
+        // The high resolution mode of SCHIP doesn't wait for the vertical blank
+        if self.quirks.display_wait() && !self.hires {
+            self.display_waiting = true;
+        }
+        Ok(())
     }
 
     // Ex9E: SKP vx
-    pub(crate) const fn op_ex9e(&mut self, opcode: u16) {
+    pub(crate) fn op_ex9e(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
 
-        let key = self.registers[vx] as usize;
+        let key = self.key(self.registers[vx])?;
 
         if self.keys[key] {
-            self.pc += 2;
+            self.skip();
         }
+        Ok(())
     }
 
     // ExA1: SKNP Vx
-    pub(crate) const fn op_exa1(&mut self, opcode: u16) {
+    pub(crate) fn op_exa1(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
 
-        let key = self.registers[vx] as usize;
+        let key = self.key(self.registers[vx])?;
 
         if !self.keys[key] {
-            self.pc += 2;
+            self.skip();
         }
+        Ok(())
+    }
+
+    // F000 nnnn: LD I, long addr
+    pub(crate) fn op_f000(&mut self) -> Result<(), ErrorKind> {
+        let high_byte = self.memory[self.address(self.pc as usize)?] as u16;
+        let low_byte = self.memory[self.address(self.pc as usize + 1)?] as u16;
+
+        self.index = (high_byte << 8) | low_byte;
+        self.pc = self.pc.wrapping_add(2);
+        Ok(())
+    }
+
+    // Fn01: PLANE n
+    pub(crate) const fn op_fn01(&mut self, opcode: u16) {
+        let planes = ((opcode & 0x0F00) >> 8) as u8;
+
+        self.planes = planes;
+    }
+
+    // F002: AUDIO
+    pub(crate) fn op_f002(&mut self) -> Result<(), ErrorKind> {
+        let start = self.index as usize;
+
+        for i in 0..AUDIO_PATTERN_SIZE {
+            self.audio_pattern[i] = self.read_memory(start + i)?;
+        }
+        Ok(())
     }
 
     // Fx07: LD Vx, DT
@@ -296,11 +428,11 @@ impl Chip8 {
 
         let mut done = false;
 
-        if !self.quirks.release || self.pressed_key.is_none() {
+        if !self.quirks.release() || self.pressed_key.is_none() {
             for i in 0..KEY_COUNT {
                 if self.keys[i] {
                     self.registers[vx] = i as u8;
-                    if !self.quirks.release {
+                    if !self.quirks.release() {
                         done = true;
                     }
                     self.pressed_key = Some(i);
@@ -309,13 +441,13 @@ impl Chip8 {
             }
         }
 
-        if self.quirks.release && self.pressed_key.is_some_and(|val| !self.keys[val]) {
+        if self.quirks.release() && self.pressed_key.is_some_and(|val| !self.keys[val]) {
             self.pressed_key = None;
             done = true;
         }
 
         if !done {
-            self.pc -= 2;
+            self.pc = self.pc.wrapping_sub(2);
         }
     }
 
@@ -341,53 +473,370 @@ impl Chip8 {
     }
 
     // Fx29: LD F, Vx
-    pub(crate) const fn op_fx29(&mut self, opcode: u16) {
+    pub(crate) fn op_fx29(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
-        let digit = self.registers[vx] as u16;
+        let digit = self.digit(self.registers[vx])?;
 
         self.index = digit * 5;
+        Ok(())
+    }
+
+    // Fx30: LD HF, Vx
+    pub(crate) fn op_fx30(&mut self, opcode: u16) -> Result<(), ErrorKind> {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+        let digit = self.digit(self.registers[vx])?;
+
+        self.index = BIG_FONT_ADDR as u16 + digit * 10;
+        Ok(())
     }
 
     // Fx33: LD B, Vx
-    pub(crate) const fn op_fx33(&mut self, opcode: u16) {
+    pub(crate) fn op_fx33(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
         let mut value = self.registers[vx];
 
         // Ones-place
-        self.memory[(self.index + 2) as usize] = value % 10;
+        self.write_memory(self.index as usize + 2, value % 10)?;
         value /= 10;
 
         // Tens-place
-        self.memory[(self.index + 1) as usize] = value % 10;
+        self.write_memory(self.index as usize + 1, value % 10)?;
         value /= 10;
 
         // Hundreds-place
-        self.memory[self.index as usize] = value % 10;
+        self.write_memory(self.index as usize, value % 10)
+    }
+
+    // Fx3A: LD PITCH, Vx
+    pub(crate) const fn op_fx3a(&mut self, opcode: u16) {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+
+        self.pitch = self.registers[vx];
     }
 
     // Fx55: LD [I], Vx
-    pub(crate) fn op_fx55(&mut self, opcode: u16) {
+    pub(crate) fn op_fx55(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
 
         for i in 0..=vx {
-            self.memory[(self.index + i as u16) as usize] = self.registers[i];
+            self.write_memory(self.index as usize + i, self.registers[i])?;
         }
 
-        if self.quirks.memory {
-            self.index = self.index + vx as u16 + 1;
+        if self.quirks.memory() {
+            self.index = self.index.wrapping_add(vx as u16 + 1);
         }
+        Ok(())
     }
 
     // Fx65: LD Vx, [I]
-    pub(crate) fn op_fx65(&mut self, opcode: u16) {
+    pub(crate) fn op_fx65(&mut self, opcode: u16) -> Result<(), ErrorKind> {
         let vx = ((opcode & 0x0F00) >> 8) as usize;
 
         for i in 0..=vx {
-            self.registers[i] = self.memory[(self.index + i as u16) as usize];
+            self.registers[i] = self.read_memory(self.index as usize + i)?;
         }
 
-        if self.quirks.memory {
-            self.index = self.index + vx as u16 + 1;
+        if self.quirks.memory() {
+            self.index = self.index.wrapping_add(vx as u16 + 1);
         }
+        Ok(())
+    }
+
+    // Fx75: LD R, Vx
+    pub(crate) fn op_fx75(&mut self, opcode: u16) {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+
+        self.rpl_flags[..=vx].copy_from_slice(&self.registers[..=vx]);
+    }
+
+    // Fx85: LD Vx, R
+    pub(crate) fn op_fx85(&mut self, opcode: u16) {
+        let vx = ((opcode & 0x0F00) >> 8) as usize;
+
+        self.registers[..=vx].copy_from_slice(&self.rpl_flags[..=vx]);
+    }
+
+    // Skips the next instruction, which is four bytes long when it is `F000 nnnn`
+    const fn skip(&mut self) {
+        let size = if self.opcode_at(self.pc) == 0xF000 {
+            4
+        } else {
+            2
+        };
+
+        self.pc = self.pc.wrapping_add(size);
+    }
+
+    // Maps an address into memory, masking it to the address space under the wrap-around quirk
+    pub(crate) const fn address(&self, addr: usize) -> Result<usize, ErrorKind> {
+        let size = self.quirks.memory_size();
+        if self.quirks.wrap_around() {
+            Ok(addr % size)
+        } else if addr < size {
+            Ok(addr)
+        } else {
+            Err(ErrorKind::MemoryOutOfBounds { addr })
+        }
+    }
+
+    fn read_memory(&mut self, addr: usize) -> Result<u8, ErrorKind> {
+        let addr = self.address(addr)?;
+        let value = self.memory[addr];
+        self.watch(addr, Access::Read, value, value);
+        Ok(value)
+    }
+
+    fn write_memory(&mut self, addr: usize, value: u8) -> Result<(), ErrorKind> {
+        let addr = self.address(addr)?;
+        let old = self.memory[addr];
+        self.memory[addr] = value;
+        self.watch(addr, Access::Write, old, value);
+        Ok(())
+    }
+
+    const fn key(&self, key: u8) -> Result<usize, ErrorKind> {
+        if self.quirks.wrap_around() {
+            Ok((key & 0xF) as usize)
+        } else if (key as usize) < KEY_COUNT {
+            Ok(key as usize)
+        } else {
+            Err(ErrorKind::InvalidKey { key })
+        }
+    }
+
+    const fn digit(&self, digit: u8) -> Result<u16, ErrorKind> {
+        if self.quirks.wrap_around() {
+            Ok((digit & 0xF) as u16)
+        } else if digit <= 0xF {
+            Ok(digit as u16)
+        } else {
+            Err(ErrorKind::InvalidDigit { digit })
+        }
+    }
+
+    // Moves the selected planes by the given amount of pixels, clearing the vacated area
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+        let source = self.framebuffer;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (src_x, src_y) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&src_x) && (0..height).contains(&src_y) {
+                    source[(src_x + width * src_y) as usize] & self.planes
+                } else {
+                    0
+                };
+
+                let idx = (x + width * y) as usize;
+                self.framebuffer[idx] = (self.framebuffer[idx] & !self.planes) | moved;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Chip8, ErrorKind, ExecuteError, Platform, Quirks};
+
+    const SPRITE_ADDR: usize = 0x300;
+
+    /// Runs one instruction per opcode on a new machine, with `0xFF` bytes at `0x300` and a
+    /// single pixel at `0x340` to draw.
+    fn run(quirks: Quirks, program: &[u16]) -> Chip8 {
+        let mut chip8 = Chip8::with_quirks(quirks);
+        chip8.memory_mut()[SPRITE_ADDR..SPRITE_ADDR + 0x40].fill(0xFF);
+        chip8.memory_mut()[SPRITE_ADDR + 0x40] = 0x80;
+        execute(&mut chip8, program);
+        chip8
+    }
+
+    /// Loads the program over the previous one and runs one instruction per opcode.
+    fn execute(chip8: &mut Chip8, program: &[u16]) {
+        let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+        chip8.load(&rom).unwrap();
+        chip8.set_pc(0x200);
+        for _ in program {
+            chip8.emulate().unwrap();
+        }
+    }
+
+    /// Runs the program on a new machine and returns the error raised by its last instruction.
+    fn fail(quirks: Quirks, program: &[u16]) -> ExecuteError {
+        let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+        let mut chip8 = Chip8::with_quirks(quirks);
+        chip8.load(&rom).unwrap();
+        for _ in 1..program.len() {
+            chip8.emulate().unwrap();
+        }
+        chip8.emulate().unwrap_err()
+    }
+
+    /// Returns the lit pixels as `(x, y, planes)`.
+    fn lit(chip8: &Chip8) -> Vec<(usize, usize, u8)> {
+        let width = chip8.width();
+        chip8
+            .framebuffer()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &pixel)| pixel != 0)
+            .map(|(idx, &pixel)| (idx % width, idx / width, pixel))
+            .collect()
+    }
+
+    #[test]
+    fn resolution() {
+        let chip8 = run(Quirks::new(), &[0x00FF, 0xA300, 0xD001]);
+        assert_eq!((chip8.width(), chip8.height()), (128, 64));
+        assert!(chip8.is_hires());
+        assert_eq!(lit(&chip8).len(), 8);
+
+        // Switching clears the screen
+        let chip8 = run(Quirks::new(), &[0x00FF, 0xA300, 0xD001, 0x00FE]);
+        assert_eq!((chip8.width(), chip8.height()), (64, 32));
+        assert!(lit(&chip8).is_empty());
+    }
+
+    #[test]
+    fn scroll() {
+        let mut chip8 = run(Quirks::new(), &[0x6008, 0x6108, 0xA340, 0xD011]);
+        assert_eq!(lit(&chip8), [(8, 8, 1)]);
+
+        execute(&mut chip8, &[0x00C2, 0x00FB]);
+        assert_eq!(lit(&chip8), [(12, 10, 1)]);
+
+        execute(&mut chip8, &[0x00FC]);
+        assert_eq!(lit(&chip8), [(8, 10, 1)]);
+    }
+
+    #[test]
+    fn large_sprites() {
+        let program = [0xA300, 0xD000];
+        let count = |quirks| lit(&run(quirks, &program)).len();
+
+        assert_eq!(count(Platform::CosmacVip.quirks()), 0);
+        assert_eq!(count(Platform::Chip48.quirks()), 0);
+        assert_eq!(count(Platform::SuperChip11.quirks()), 8 * 16);
+        assert_eq!(count(Platform::SuperChipModern.quirks()), 16 * 16);
+
+        let chip8 = run(Platform::SuperChip11.quirks(), &[0x00FF, 0xA300, 0xD000]);
+        assert_eq!(lit(&chip8).len(), 16 * 16);
+    }
+
+    #[test]
+    fn big_font_and_flags() {
+        let chip8 = run(Quirks::new(), &[0x6007, 0xF030]);
+        assert_eq!(chip8.index(), crate::BIG_FONT_ADDR as u16 + 70);
+
+        let mut chip8 = run(
+            Quirks::new(),
+            &[0x6001, 0x6102, 0xF175, 0x6000, 0x6100, 0xF085],
+        );
+        assert_eq!((chip8.register(0), chip8.register(1)), (1, 0));
+
+        execute(&mut chip8, &[0x00FD]);
+        assert!(chip8.is_halted());
+    }
+
+    #[test]
+    fn planes() {
+        // Both planes take a sprite from memory, one after the other
+        let mut chip8 = run(Platform::XoChip.quirks(), &[0xF301, 0xA300, 0xD001]);
+        assert_eq!(lit(&chip8).len(), 8);
+        assert!(lit(&chip8).iter().all(|&(_, _, planes)| planes == 3));
+
+        // Clearing only touches the selected planes
+        execute(&mut chip8, &[0xF101, 0x00E0]);
+        assert!(lit(&chip8).iter().all(|&(_, _, planes)| planes == 2));
+
+        let chip8 = run(Platform::XoChip.quirks(), &[0xF201, 0xA340, 0xD001]);
+        assert_eq!(lit(&chip8), [(0, 0, 2)]);
+    }
+
+    #[test]
+    fn scroll_up() {
+        let mut chip8 = run(Platform::XoChip.quirks(), &[0x6002, 0x6104, 0xA340, 0xD011]);
+        execute(&mut chip8, &[0x00D3]);
+        assert_eq!(lit(&chip8), [(2, 1, 1)]);
+    }
+
+    #[test]
+    fn register_ranges() {
+        let mut chip8 = run(
+            Platform::XoChip.quirks(),
+            &[0x6101, 0x6202, 0x6303, 0xA380, 0x5132, 0xA390, 0x5312],
+        );
+        assert_eq!(chip8.memory()[0x380..0x383], [1, 2, 3]);
+        // Reversed ranges go from vX down to vY
+        assert_eq!(chip8.memory()[0x390..0x393], [3, 2, 1]);
+        // The index register is left alone
+        assert_eq!(chip8.index(), 0x390);
+
+        execute(&mut chip8, &[0xA380, 0x5353]);
+        assert_eq!(
+            [chip8.register(3), chip8.register(4), chip8.register(5)],
+            [1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn long_index_and_audio() {
+        let mut chip8 = Chip8::with_quirks(Platform::XoChip.quirks());
+        chip8.load(&[0xF0, 0x00, 0x12, 0x34]).unwrap();
+        chip8.emulate().unwrap();
+        assert_eq!((chip8.index(), chip8.pc()), (0x1234, 0x204));
+
+        let mut chip8 = run(Platform::XoChip.quirks(), &[0x6080, 0xF03A, 0xA300, 0xF002]);
+        assert_eq!(chip8.pitch(), 0x80);
+        assert_eq!(chip8.audio_pattern(), &[0xFF; 16]);
+
+        // A skip steps over both words of `F000 nnnn`
+        chip8
+            .load(&[0x30, 0x80, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01])
+            .unwrap();
+        chip8.set_pc(0x200);
+        chip8.emulate().unwrap();
+        assert_eq!(chip8.pc(), 0x206);
+    }
+
+    #[test]
+    fn bounds() {
+        let quirks = Quirks::new();
+        let wrapping = quirks.with_wrap_around(true);
+
+        // Fetching the last byte of memory
+        let mut chip8 = Chip8::with_quirks(quirks);
+        chip8.memory_mut()[0xFFF] = 0x12;
+        chip8.set_pc(0xFFF);
+        let err = chip8.emulate().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MemoryOutOfBounds { addr: 0x1000 });
+        assert_eq!(err.opcode(), None);
+
+        let mut chip8 = Chip8::with_quirks(wrapping);
+        chip8.memory_mut()[0xFFF] = 0x12;
+        chip8.set_pc(0xFFF);
+        chip8.emulate().unwrap();
+        assert_eq!(chip8.pc(), 0x2F0);
+
+        // Storing past the end of memory
+        let program = [0x6007, 0x6108, 0xAFFF, 0xF155];
+        let err = fail(quirks, &program);
+        assert_eq!(err.kind(), ErrorKind::MemoryOutOfBounds { addr: 0x1000 });
+        assert_eq!(err.opcode(), Some(0xF155));
+
+        let chip8 = run(wrapping, &program);
+        assert_eq!(chip8.memory()[0xFFF], 7);
+        assert_eq!(chip8.memory()[0], 8);
+
+        // Checking a key above F
+        let program = [0x6012, 0xE09E];
+        let err = fail(quirks, &program);
+        assert_eq!(err.kind(), ErrorKind::InvalidKey { key: 0x12 });
+
+        let mut chip8 = Chip8::with_quirks(wrapping);
+        chip8.set_key(2, true);
+        execute(&mut chip8, &program);
+        assert_eq!(chip8.pc(), 0x206);
     }
 }
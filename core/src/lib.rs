@@ -1,16 +1,67 @@
 #![allow(clippy::cast_lossless)]
 
+mod analyze;
+mod asm;
+mod audio;
+mod crash;
+mod debugger;
+mod decode;
+mod decompile;
+mod disasm;
+mod flow;
+mod inspect;
 mod instructions;
+mod movie;
+mod quirks;
+mod rewind;
+mod rng;
+mod state;
+mod trace;
+mod undefined;
+mod watch;
+mod wav;
 
-pub const VIDEO_WIDTH: usize = 64;
-pub const VIDEO_HEIGHT: usize = 32;
+pub use analyze::{Analysis, BasicBlock, Edge, Quirk, QuirkUse, analyze};
+pub use asm::{AssembleError, Program, assemble};
+pub use audio::{AudioRenderer, Waveform};
+pub use crash::{ErrorKind, ExecuteError, Executed};
+pub use debugger::{Debugger, OpcodePattern, ParseOpcodePatternError, StopReason};
+pub use decode::{Instruction, Mnemonic, Syntax};
+pub use decompile::decompile;
+pub use disasm::{Line, LineKind, Listing, disassemble};
+pub use flow::EdgeKind;
+pub use inspect::Registers;
+pub use movie::{Movie, MovieError};
+pub use quirks::{ParsePlatformError, Platform, Quirks};
+pub use rewind::RewindBuffer;
+pub use state::StateError;
+pub use trace::{
+    ParseTraceFormatError, TraceBuffer, TraceEntry, TraceFormat, TraceSink, TraceWriter,
+};
+pub use undefined::{TrapHandler, UndefinedPolicy};
+pub use watch::{Access, WatchHit, Watchpoint};
+pub use wav::WavRecorder;
+
+use crash::HISTORY_SIZE;
+use rng::Random;
+use std::collections::VecDeque;
+
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
 
 const START_ADDR: usize = 0x200;
-const MEMORY_SIZE: usize = 4096;
+const MEMORY_SIZE: usize = 0x10000;
 const REGISTER_COUNT: usize = 16;
 const STACK_SIZE: usize = 16;
 const KEY_COUNT: usize = 16;
 const FONT_SET_SIZE: usize = 80;
+const BIG_FONT_ADDR: usize = FONT_SET_SIZE;
+const BIG_FONT_SET_SIZE: usize = 160;
+const RPL_FLAG_COUNT: usize = 16;
+const AUDIO_PATTERN_SIZE: usize = 16;
+const DEFAULT_PITCH: u8 = 64;
 
 const FONT_SET: [u8; FONT_SET_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -31,6 +82,25 @@ const FONT_SET: [u8; FONT_SET_SIZE] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+const BIG_FONT_SET: [u8; BIG_FONT_SET_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 #[derive(Debug)]
 pub struct Chip8 {
     memory: [u8; MEMORY_SIZE],
@@ -42,18 +112,54 @@ pub struct Chip8 {
     delay_timer: u8,
     sound_timer: u8,
     keys: [bool; KEY_COUNT],
-    framebuffer: [bool; VIDEO_WIDTH * VIDEO_HEIGHT],
+    // Each pixel holds a bitmask of the planes it is lit on
+    framebuffer: [u8; HIRES_WIDTH * HIRES_HEIGHT],
+    hires: bool,
+    // Bitmask of the planes selected by `Fn01`
+    planes: u8,
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
+    // Set by `00FD`, execution stops until the machine is reset
+    halted: bool,
+    // Set by `Dxyn` under the display wait quirk, execution stops until the timers are ticked
+    display_waiting: bool,
+    // SUPER-CHIP persistent user flags, kept across resets like the HP 48 RPL storage
+    rpl_flags: [u8; RPL_FLAG_COUNT],
     quirks: Quirks,
     // Used to check if pressed key is released
     pressed_key: Option<usize>,
+    rom_hash: u64,
+    rng: Random,
+    undefined_policy: UndefinedPolicy,
+    // Instructions executed since the last reset
+    cycles: u64,
+    // Instructions executed since the timers were last ticked
+    frame_cycles: u32,
+    // Whether the sound timer was running at the last timer tick
+    buzzer_active: bool,
+    // The instruction being executed, reported by watchpoint hits and errors
+    instruction_pc: u16,
+    opcode: u16,
+    // The last executed instructions, oldest first
+    history: VecDeque<Executed>,
+    watchpoints: Vec<Watchpoint>,
+    watch_hits: Vec<WatchHit>,
+    tracer: Option<Box<dyn TraceSink>>,
 }
 
 impl Chip8 {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_quirks(Quirks::new())
+    }
+
+    #[must_use]
+    pub fn with_quirks(quirks: Quirks) -> Self {
         let mut memory = [0; MEMORY_SIZE];
 
         memory[..FONT_SET_SIZE].copy_from_slice(&FONT_SET[..]);
+        memory[BIG_FONT_ADDR..(BIG_FONT_ADDR + BIG_FONT_SET_SIZE)]
+            .copy_from_slice(&BIG_FONT_SET[..]);
 
         Self {
             memory,
@@ -65,9 +171,28 @@ impl Chip8 {
             delay_timer: 0,
             sound_timer: 0,
             keys: [false; KEY_COUNT],
-            framebuffer: [false; VIDEO_WIDTH * VIDEO_HEIGHT],
-            quirks: Quirks::new(),
+            framebuffer: [0; HIRES_WIDTH * HIRES_HEIGHT],
+            hires: false,
+            planes: 0x1,
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+            halted: false,
+            display_waiting: false,
+            rpl_flags: [0; RPL_FLAG_COUNT],
+            quirks,
             pressed_key: None,
+            rom_hash: 0,
+            rng: Random::new(),
+            undefined_policy: UndefinedPolicy::Halt,
+            cycles: 0,
+            frame_cycles: 0,
+            buzzer_active: false,
+            instruction_pc: START_ADDR as u16,
+            opcode: 0,
+            history: VecDeque::with_capacity(HISTORY_SIZE),
+            watchpoints: Vec::new(),
+            watch_hits: Vec::new(),
+            tracer: None,
         }
     }
 
@@ -81,18 +206,132 @@ impl Chip8 {
         self.delay_timer = 0;
         self.sound_timer = 0;
         self.keys = [false; KEY_COUNT];
-        self.framebuffer = [false; VIDEO_WIDTH * VIDEO_HEIGHT];
+        self.framebuffer = [0; HIRES_WIDTH * HIRES_HEIGHT];
+        self.hires = false;
+        self.planes = 0x1;
+        self.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+        self.pitch = DEFAULT_PITCH;
+        self.halted = false;
+        self.display_waiting = false;
+        self.rom_hash = 0;
+        self.rng.restart();
+        self.cycles = 0;
+        self.frame_cycles = 0;
+        self.buzzer_active = false;
+        self.instruction_pc = START_ADDR as u16;
+        self.opcode = 0;
+        self.history.clear();
+        self.watch_hits.clear();
 
         self.memory[..FONT_SET_SIZE].copy_from_slice(&FONT_SET[..]);
+        self.memory[BIG_FONT_ADDR..(BIG_FONT_ADDR + BIG_FONT_SET_SIZE)]
+            .copy_from_slice(&BIG_FONT_SET[..]);
     }
 
-    pub fn load(&mut self, data: &[u8]) {
+    /// Copies a ROM into memory at address `0x200`.
+    ///
+    /// The ROM must fit in the memory of the variant selected by the quirks. Nothing is loaded
+    /// when an error is returned.
+    pub fn load(&mut self, data: &[u8]) -> Result<(), LoadError> {
+        let available = self.quirks.memory_size() - START_ADDR;
+        match data.len() {
+            0 => return Err(LoadError::Empty),
+            size if size > MEMORY_SIZE - START_ADDR => return Err(LoadError::TooLarge { size }),
+            size if size > available => return Err(LoadError::ExceedsMemory { size, available }),
+            _ => {}
+        }
+
         self.memory[START_ADDR..(START_ADDR + data.len())].copy_from_slice(data);
+        self.rom_hash = state::hash(data);
+        Ok(())
+    }
+
+    /// Returns the seed of the random number generator used by `Cxkk`, or `None` when a custom
+    /// generator was provided with [`Chip8::set_rng`].
+    #[must_use]
+    pub const fn seed(&self) -> Option<u64> {
+        self.rng.seed()
+    }
+
+    /// Seeds the random number generator used by `Cxkk`, making runs reproducible.
+    ///
+    /// The generator is rewound to this seed on every [`Chip8::reset`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Random::from_seed(seed);
+    }
+
+    /// Replaces the random number generator used by `Cxkk`.
+    ///
+    /// The state of a custom generator is not part of save states.
+    pub fn set_rng(&mut self, rng: impl rand::Rng + Send + 'static) {
+        self.rng = Random::from_rng(rng);
+    }
+
+    #[must_use]
+    pub const fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub const fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Returns the pixels of the current resolution in row-major order.
+    ///
+    /// Each pixel is a bitmask of the planes it is lit on, so plain CHIP-8 and SUPER-CHIP
+    /// programs only produce `0` and `1` while XO-CHIP programs can use up to four planes.
+    #[must_use]
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer[..(self.width() * self.height())]
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        if self.hires { HIRES_WIDTH } else { LORES_WIDTH }
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    /// Returns the address of the next instruction.
+    #[must_use]
+    pub const fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    #[must_use]
+    pub const fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    #[must_use]
+    pub const fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Returns whether a draw is waiting for the display, which holds execution until the
+    /// timers are next ticked.
+    #[must_use]
+    pub const fn is_display_waiting(&self) -> bool {
+        self.display_waiting
+    }
+
+    /// Returns the 1-bit sample pattern loaded by the XO-CHIP `F002` instruction.
+    #[must_use]
+    pub const fn audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.audio_pattern
     }
 
+    /// Returns the XO-CHIP pitch register set by `Fx3A`.
     #[must_use]
-    pub const fn framebuffer(&self) -> &[bool] {
-        &self.framebuffer
+    pub const fn pitch(&self) -> u8 {
+        self.pitch
     }
 
     pub const fn set_key(&mut self, idx: usize, pressed: bool) {
@@ -100,16 +339,51 @@ impl Chip8 {
     }
 
     pub fn emulate(&mut self) -> Result<(), ExecuteError> {
+        if self.halted || self.display_waiting {
+            return Ok(());
+        }
+
+        self.watch_hits.clear();
+
         // Fetch
-        let opcode = self.fetch();
+        self.instruction_pc = self.pc;
+        let opcode = self.fetch().map_err(|kind| self.error(kind, None))?;
+        self.opcode = opcode;
+        self.record_history(self.instruction_pc, opcode);
+        self.trace(opcode);
 
         // Decode and Execute
-        self.execute(opcode)?;
+        self.cycles += 1;
+        self.frame_cycles = self.frame_cycles.saturating_add(1);
+        self.execute(opcode)
+            .map_err(|kind| self.error(kind, Some(opcode)))
+    }
 
+    /// Runs one 60 Hz frame: executes instructions until `instructions_per_frame` of them have
+    /// run since the timers were last ticked, then ticks the timers once.
+    ///
+    /// The frame ends early when a draw waits for the display.
+    pub fn run_frame(&mut self, instructions_per_frame: u32) -> Result<(), ExecuteError> {
+        while self.frame_cycles < instructions_per_frame && !self.halted && !self.display_waiting {
+            self.emulate()?;
+        }
+
+        self.tick_timers();
         Ok(())
     }
 
+    /// Returns the number of instructions executed since the timers were last ticked.
+    #[must_use]
+    pub const fn frame_cycles(&self) -> u32 {
+        self.frame_cycles
+    }
+
+    /// Ticks the timers and starts a new frame.
     pub const fn tick_timers(&mut self) {
+        self.frame_cycles = 0;
+        self.display_waiting = false;
+        self.buzzer_active = self.sound_timer > 0;
+
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -119,56 +393,74 @@ impl Chip8 {
         }
     }
 
-    const fn fetch(&mut self) -> u16 {
-        let high_byte = self.memory[self.pc as usize] as u16;
-        let low_byte = self.memory[(self.pc + 1) as usize] as u16;
+    fn fetch(&mut self) -> Result<u16, ErrorKind> {
+        let high_byte = self.memory[self.address(self.pc as usize)?] as u16;
+        let low_byte = self.memory[self.address(self.pc as usize + 1)?] as u16;
         let opcode = (high_byte << 8) | low_byte;
-        self.pc += 2;
-        opcode
-    }
-
-    fn execute(&mut self, opcode: u16) -> Result<(), ExecuteError> {
-        match (
-            (opcode & 0xF000) >> 12,
-            (opcode & 0x0F00) >> 8,
-            (opcode & 0x00F0) >> 4,
-            opcode & 0x000F,
-        ) {
-            (0x0, 0x0, 0xE, 0x0) => self.op_00e0(),
-            (0x0, 0x0, 0xE, 0xE) => self.op_00ee()?,
-            (0x1, _, _, _) => self.op_1nnn(opcode),
-            (0x2, _, _, _) => self.op_2nnn(opcode)?,
-            (0x3, _, _, _) => self.op_3xkk(opcode),
-            (0x4, _, _, _) => self.op_4xkk(opcode),
-            (0x5, _, _, _) => self.op_5xy0(opcode),
-            (0x6, _, _, _) => self.op_6xkk(opcode),
-            (0x7, _, _, _) => self.op_7xkk(opcode),
-            (0x8, _, _, 0x0) => self.op_8xy0(opcode),
-            (0x8, _, _, 0x1) => self.op_8xy1(opcode),
-            (0x8, _, _, 0x2) => self.op_8xy2(opcode),
-            (0x8, _, _, 0x3) => self.op_8xy3(opcode),
-            (0x8, _, _, 0x4) => self.op_8xy4(opcode),
-            (0x8, _, _, 0x5) => self.op_8xy5(opcode),
-            (0x8, _, _, 0x6) => self.op_8xy6(opcode),
-            (0x8, _, _, 0x7) => self.op_8xy7(opcode),
-            (0x8, _, _, 0xE) => self.op_8xye(opcode),
-            (0x9, _, _, 0x0) => self.op_9xy0(opcode),
-            (0xA, _, _, _) => self.op_annn(opcode),
-            (0xB, _, _, _) => self.op_bnnn(opcode),
-            (0xC, _, _, _) => self.op_cxkk(opcode),
-            (0xD, _, _, _) => self.op_dxyn(opcode),
-            (0xE, _, 0x9, 0xE) => self.op_ex9e(opcode),
-            (0xE, _, 0xA, 0x1) => self.op_exa1(opcode),
-            (0xF, _, 0x0, 0x7) => self.op_fx07(opcode),
-            (0xF, _, 0x0, 0xA) => self.op_fx0a(opcode),
-            (0xF, _, 0x1, 0x5) => self.op_fx15(opcode),
-            (0xF, _, 0x1, 0x8) => self.op_fx18(opcode),
-            (0xF, _, 0x1, 0xE) => self.op_fx1e(opcode),
-            (0xF, _, 0x2, 0x9) => self.op_fx29(opcode),
-            (0xF, _, 0x3, 0x3) => self.op_fx33(opcode),
-            (0xF, _, 0x5, 0x5) => self.op_fx55(opcode),
-            (0xF, _, 0x6, 0x5) => self.op_fx65(opcode),
-            _ => return Err(ExecuteError::UndefinedInstruction(opcode)),
+        self.pc = self.pc.wrapping_add(2);
+        Ok(opcode)
+    }
+
+    /// Reads the opcode stored at `addr` without executing it.
+    pub(crate) const fn opcode_at(&self, addr: u16) -> u16 {
+        let high_byte = self.memory[addr as usize] as u16;
+        let low_byte = self.memory[(addr as usize + 1) % MEMORY_SIZE] as u16;
+        (high_byte << 8) | low_byte
+    }
+
+    fn execute(&mut self, opcode: u16) -> Result<(), ErrorKind> {
+        match Instruction::decode(opcode) {
+            Instruction::ClearScreen => self.op_00e0(),
+            Instruction::Return => self.op_00ee()?,
+            Instruction::ScrollDown { .. } => self.op_00cn(opcode),
+            Instruction::ScrollUp { .. } => self.op_00dn(opcode),
+            Instruction::ScrollRight => self.op_00fb(),
+            Instruction::ScrollLeft => self.op_00fc(),
+            Instruction::Exit => self.op_00fd(),
+            Instruction::Lores => self.op_00fe(),
+            Instruction::Hires => self.op_00ff(),
+            Instruction::Jump { .. } => self.op_1nnn(opcode),
+            Instruction::Call { .. } => self.op_2nnn(opcode)?,
+            Instruction::SkipEqualByte { .. } => self.op_3xkk(opcode),
+            Instruction::SkipNotEqualByte { .. } => self.op_4xkk(opcode),
+            Instruction::SkipEqual { .. } => self.op_5xy0(opcode),
+            Instruction::SaveRange { .. } => self.op_5xy2(opcode)?,
+            Instruction::LoadRange { .. } => self.op_5xy3(opcode)?,
+            Instruction::LoadByte { .. } => self.op_6xkk(opcode),
+            Instruction::AddByte { .. } => self.op_7xkk(opcode),
+            Instruction::Move { .. } => self.op_8xy0(opcode),
+            Instruction::Or { .. } => self.op_8xy1(opcode),
+            Instruction::And { .. } => self.op_8xy2(opcode),
+            Instruction::Xor { .. } => self.op_8xy3(opcode),
+            Instruction::Add { .. } => self.op_8xy4(opcode),
+            Instruction::Sub { .. } => self.op_8xy5(opcode),
+            Instruction::ShiftRight { .. } => self.op_8xy6(opcode),
+            Instruction::SubN { .. } => self.op_8xy7(opcode),
+            Instruction::ShiftLeft { .. } => self.op_8xye(opcode),
+            Instruction::SkipNotEqual { .. } => self.op_9xy0(opcode),
+            Instruction::LoadIndex { .. } => self.op_annn(opcode),
+            Instruction::JumpOffset { .. } => self.op_bnnn(opcode),
+            Instruction::Random { .. } => self.op_cxkk(opcode),
+            Instruction::Draw { .. } => self.op_dxyn(opcode)?,
+            Instruction::SkipKey { .. } => self.op_ex9e(opcode)?,
+            Instruction::SkipNotKey { .. } => self.op_exa1(opcode)?,
+            Instruction::LoadIndexLong => self.op_f000()?,
+            Instruction::Plane { .. } => self.op_fn01(opcode),
+            Instruction::Audio => self.op_f002()?,
+            Instruction::LoadDelay { .. } => self.op_fx07(opcode),
+            Instruction::WaitKey { .. } => self.op_fx0a(opcode),
+            Instruction::SetDelay { .. } => self.op_fx15(opcode),
+            Instruction::SetSound { .. } => self.op_fx18(opcode),
+            Instruction::AddIndex { .. } => self.op_fx1e(opcode),
+            Instruction::Font { .. } => self.op_fx29(opcode)?,
+            Instruction::BigFont { .. } => self.op_fx30(opcode)?,
+            Instruction::Bcd { .. } => self.op_fx33(opcode)?,
+            Instruction::SetPitch { .. } => self.op_fx3a(opcode),
+            Instruction::Store { .. } => self.op_fx55(opcode)?,
+            Instruction::Restore { .. } => self.op_fx65(opcode)?,
+            Instruction::SaveFlags { .. } => self.op_fx75(opcode),
+            Instruction::LoadFlags { .. } => self.op_fx85(opcode),
+            Instruction::Sys { .. } | Instruction::Unknown(_) => self.op_undefined(opcode)?,
         }
         Ok(())
     }
@@ -181,49 +473,150 @@ impl Default for Chip8 {
 }
 
 #[derive(Debug)]
-struct Quirks {
-    /// The AND, OR and XOR opcodes (`8xy1`, `8xy2` and `8xy3`) reset the flags register to zero.
-    vf_reset: bool,
-    /// The save and load opcodes (`Fx55` and `Fx65`) increment the index register.
-    memory: bool,
-    /// Sprites drawn at the bottom edge of the screen get clipped instead of wrapping around the screen.
-    clipping: bool,
-    /// The shift opcodes (`8xy6` and `8xyE`) only operate on vX instead of storing the shifted version of vY in vX.
-    shifting: bool,
-    /// The jump instruction (`Bnnn`) doesn't use v0, but vX instead where X is the highest nibble of nnn.
-    jumping: bool,
-    /// The get key instruction (`Fx0A`) waits for a key press and key up.
-    release: bool,
+pub enum LoadError {
+    Empty,
+    /// The ROM doesn't fit in the address space of any variant.
+    TooLarge {
+        size: usize,
+    },
+    /// The ROM doesn't fit in the memory of the selected variant.
+    ExceedsMemory {
+        size: usize,
+        available: usize,
+    },
 }
 
-impl Quirks {
-    pub const fn new() -> Self {
-        Self {
-            vf_reset: true,
-            memory: true,
-            clipping: true,
-            shifting: false,
-            jumping: false,
-            release: true,
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "ROM is empty"),
+            Self::TooLarge { size } => write!(
+                f,
+                "ROM is {size} bytes, larger than the {} bytes any variant can load",
+                MEMORY_SIZE - START_ADDR
+            ),
+            Self::ExceedsMemory { size, available } => write!(
+                f,
+                "ROM is {size} bytes, but only {available} bytes of memory are available"
+            ),
         }
     }
 }
 
-#[derive(Debug)]
-pub enum ExecuteError {
-    UndefinedInstruction(u16),
-    StackOverflow,
-    StackUnderflow,
-}
+impl std::error::Error for LoadError {}
 
-impl std::fmt::Display for ExecuteError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Self::UndefinedInstruction(opcode) => write!(f, "Undefined instruction {opcode:#04x}"),
-            Self::StackOverflow => write!(f, "Stack overflow"),
-            Self::StackUnderflow => write!(f, "Stack underflow"),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chip8(quirks: Quirks, program: &[u16]) -> Chip8 {
+        let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+        let mut chip8 = Chip8::new();
+        chip8.set_quirks(quirks);
+        chip8.load(&rom).unwrap();
+        chip8
     }
-}
 
-impl std::error::Error for ExecuteError {}
+    #[test]
+    fn run_frame() {
+        // Sets the delay timer to 5 and loops
+        let mut chip8 = chip8(Quirks::new(), &[0x6005, 0xF015, 0x1204]);
+
+        chip8.run_frame(10).unwrap();
+        assert_eq!(chip8.cycles(), 10);
+        assert_eq!(chip8.frame_cycles(), 0);
+        assert_eq!(chip8.delay_timer(), 4);
+
+        chip8.run_frame(10).unwrap();
+        assert_eq!(chip8.cycles(), 20);
+        assert_eq!(chip8.delay_timer(), 3);
+    }
+
+    #[test]
+    fn run_frame_after_exit() {
+        let mut chip8 = chip8(Quirks::new(), &[0x6002, 0xF015, 0x00FD]);
+
+        chip8.run_frame(10).unwrap();
+        assert!(chip8.is_halted());
+        assert_eq!(chip8.cycles(), 3);
+
+        // The timers keep running while halted
+        chip8.run_frame(10).unwrap();
+        assert_eq!(chip8.cycles(), 3);
+        assert_eq!(chip8.delay_timer(), 0);
+    }
+
+    #[test]
+    fn display_wait() {
+        let program = [0xA000, 0xD005, 0xD005, 0x1206];
+        let mut chip8 = chip8(Platform::CosmacVip.quirks(), &program);
+
+        chip8.emulate().unwrap();
+        chip8.emulate().unwrap();
+        assert!(chip8.is_display_waiting());
+
+        // Stalled until the next frame
+        chip8.emulate().unwrap();
+        assert_eq!(chip8.cycles(), 2);
+        assert_eq!(chip8.pc(), 0x204);
+
+        chip8.tick_timers();
+        assert!(!chip8.is_display_waiting());
+        chip8.emulate().unwrap();
+        assert_eq!(chip8.cycles(), 3);
+    }
+
+    #[test]
+    fn display_wait_ends_frame() {
+        let mut chip8 = chip8(
+            Platform::CosmacVip.quirks(),
+            &[0xA000, 0xD005, 0xD005, 0x1206],
+        );
+        chip8.run_frame(100).unwrap();
+        assert_eq!(chip8.cycles(), 2);
+        assert!(!chip8.is_display_waiting());
+
+        chip8.run_frame(100).unwrap();
+        assert_eq!(chip8.cycles(), 3);
+    }
+
+    #[test]
+    fn no_display_wait() {
+        let program = [0xA000, 0xD005, 0xD005, 0x1206];
+        let mut default = chip8(Quirks::new(), &program);
+        default.run_frame(100).unwrap();
+        assert_eq!(default.cycles(), 100);
+
+        // Only lores draws wait
+        let mut schip = chip8(Platform::SuperChip11.quirks(), &[0x00FF, 0xD005, 0x1202]);
+        schip.run_frame(100).unwrap();
+        assert_eq!(schip.cycles(), 100);
+    }
+
+    #[test]
+    fn load_errors() {
+        let mut chip8 = Chip8::new();
+        assert!(matches!(chip8.load(&[]), Err(LoadError::Empty)));
+
+        let rom = vec![0xAA; MEMORY_SIZE - START_ADDR + 1];
+        assert!(matches!(
+            chip8.load(&rom),
+            Err(LoadError::TooLarge { size }) if size == rom.len()
+        ));
+
+        // Fits the XO-CHIP address space, but not the 4 KiB of the VIP
+        let rom = &rom[..0x1000];
+        assert!(matches!(
+            chip8.load(rom),
+            Err(LoadError::ExceedsMemory {
+                size: 0x1000,
+                available: 0xE00
+            })
+        ));
+        assert!(chip8.memory()[START_ADDR..].iter().all(|&byte| byte == 0));
+
+        chip8.set_quirks(Platform::XoChip.quirks());
+        chip8.load(rom).unwrap();
+        assert_eq!(chip8.memory()[START_ADDR + 0xFFF], 0xAA);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::{Chip8, KEY_COUNT, LoadError, Quirks, RPL_FLAG_COUNT, UndefinedPolicy, state};
+
+const MAGIC: [u8; 4] = *b"C8MV";
+const FORMAT_VERSION: u16 = 1;
+
+/// A recording of the keypad, frame by frame, that replays a session exactly.
+///
+/// The file starts with a header holding the format version, the hash of the ROM, the quirks,
+/// the undefined opcode policy, the seed of the random number generator, the instructions per
+/// frame and the hash of the screen after the last frame, followed by the keypad of every frame
+/// as a little-endian `u16` with bit `n` set while key `n` is held.
+///
+/// Recording and playback both start from a freshly reset machine with cleared RPL flags, so a
+/// movie plays back the same on any host that runs frames with [`Chip8::run_frame`]. A trap
+/// handler can't be stored, so a movie recorded with one needs the same handler installed
+/// before playback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    rom_hash: u64,
+    quirks: Quirks,
+    policy: Policy,
+    seed: u64,
+    instructions_per_frame: u32,
+    screen_hash: u64,
+    frames: Vec<u16>,
+}
+
+/// The undefined opcode policy without the trap handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Halt,
+    Ignore,
+    Trap,
+}
+
+impl Policy {
+    const fn of(policy: &UndefinedPolicy) -> Self {
+        match policy {
+            UndefinedPolicy::Halt => Self::Halt,
+            UndefinedPolicy::Ignore => Self::Ignore,
+            UndefinedPolicy::Trap(_) => Self::Trap,
+        }
+    }
+
+    const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Halt),
+            1 => Some(Self::Ignore),
+            2 => Some(Self::Trap),
+            _ => None,
+        }
+    }
+}
+
+impl Movie {
+    /// Resets the machine, clears the RPL flags, seeds it and loads the ROM, then starts an
+    /// empty recording.
+    pub fn record(
+        chip8: &mut Chip8,
+        rom: &[u8],
+        seed: u64,
+        instructions_per_frame: u32,
+    ) -> Result<Self, LoadError> {
+        chip8.reset();
+        chip8.rpl_flags = [0; RPL_FLAG_COUNT];
+        chip8.set_seed(seed);
+        chip8.load(rom)?;
+
+        Ok(Self {
+            rom_hash: chip8.rom_hash(),
+            quirks: chip8.quirks(),
+            policy: Policy::of(chip8.undefined_policy()),
+            seed,
+            instructions_per_frame,
+            screen_hash: screen_hash(chip8),
+            frames: Vec::new(),
+        })
+    }
+
+    /// Appends the keys held during the frame that just ran. Call it once per frame, after the
+    /// timers are ticked.
+    pub fn record_frame(&mut self, chip8: &Chip8) {
+        let keys = chip8
+            .keys()
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (key, &pressed)| {
+                mask | (u16::from(pressed) << key)
+            });
+        self.frames.push(keys);
+        self.screen_hash = screen_hash(chip8);
+    }
+
+    /// Resets the machine to the state the recording started from.
+    pub fn start(&self, chip8: &mut Chip8, rom: &[u8]) -> Result<(), MovieError> {
+        let rom_hash = state::hash(rom);
+        if rom_hash != self.rom_hash {
+            return Err(MovieError::RomMismatch {
+                expected: self.rom_hash,
+                found: rom_hash,
+            });
+        }
+
+        match self.policy {
+            Policy::Halt => chip8.set_undefined_policy(UndefinedPolicy::Halt),
+            Policy::Ignore => chip8.set_undefined_policy(UndefinedPolicy::Ignore),
+            Policy::Trap => {
+                if Policy::of(chip8.undefined_policy()) != Policy::Trap {
+                    return Err(MovieError::TrapRequired);
+                }
+            }
+        }
+
+        chip8.reset();
+        chip8.rpl_flags = [0; RPL_FLAG_COUNT];
+        chip8.set_quirks(self.quirks);
+        chip8.set_seed(self.seed);
+        chip8.load(rom).map_err(MovieError::Load)
+    }
+
+    /// Sets the keys held during `frame`. Returns `false` once the movie is over.
+    pub fn apply_frame(&self, chip8: &mut Chip8, frame: usize) -> bool {
+        let Some(&keys) = self.frames.get(frame) else {
+            return false;
+        };
+        for key in 0..KEY_COUNT {
+            chip8.set_key(key, keys & (1 << key) != 0);
+        }
+        true
+    }
+
+    /// Checks that the machine shows the same screen as at the end of the recording.
+    pub fn verify(&self, chip8: &Chip8) -> Result<(), MovieError> {
+        let found = screen_hash(chip8);
+        if found != self.screen_hash {
+            return Err(MovieError::Desync {
+                expected: self.screen_hash,
+                found,
+            });
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    #[must_use]
+    pub const fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[must_use]
+    pub const fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Returns the number of recorded frames.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Serializes the movie.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(41 + self.frames.len() * 2);
+
+        data.extend_from_slice(&MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&self.rom_hash.to_le_bytes());
+        data.extend_from_slice(&self.quirks.to_bits().to_le_bytes());
+        data.push(self.policy as u8);
+        data.extend_from_slice(&self.seed.to_le_bytes());
+        data.extend_from_slice(&self.instructions_per_frame.to_le_bytes());
+        data.extend_from_slice(&self.screen_hash.to_le_bytes());
+        for keys in &self.frames {
+            data.extend_from_slice(&keys.to_le_bytes());
+        }
+
+        data
+    }
+
+    /// Parses a movie created by [`Movie::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MovieError> {
+        let mut data = data;
+
+        if read::<4>(&mut data)? != MAGIC {
+            return Err(MovieError::InvalidMagic);
+        }
+        let version = u16::from_le_bytes(read(&mut data)?);
+        if version != FORMAT_VERSION {
+            return Err(MovieError::UnsupportedVersion(version));
+        }
+
+        let rom_hash = u64::from_le_bytes(read(&mut data)?);
+        let quirks = Quirks::from_bits(u16::from_le_bytes(read(&mut data)?));
+        let [policy] = read(&mut data)?;
+        let policy = Policy::from_byte(policy).ok_or(MovieError::Corrupt)?;
+        let seed = u64::from_le_bytes(read(&mut data)?);
+        let instructions_per_frame = u32::from_le_bytes(read(&mut data)?);
+        let screen_hash = u64::from_le_bytes(read(&mut data)?);
+
+        if !data.len().is_multiple_of(2) {
+            return Err(MovieError::Truncated);
+        }
+        let frames = data
+            .chunks_exact(2)
+            .map(|keys| u16::from_le_bytes([keys[0], keys[1]]))
+            .collect();
+
+        Ok(Self {
+            rom_hash,
+            quirks,
+            policy,
+            seed,
+            instructions_per_frame,
+            screen_hash,
+            frames,
+        })
+    }
+}
+
+fn read<const N: usize>(data: &mut &[u8]) -> Result<[u8; N], MovieError> {
+    let (head, tail) = data.split_first_chunk::<N>().ok_or(MovieError::Truncated)?;
+    *data = tail;
+    Ok(*head)
+}
+
+fn screen_hash(chip8: &Chip8) -> u64 {
+    state::hash(chip8.framebuffer())
+}
+
+#[derive(Debug)]
+pub enum MovieError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    Corrupt,
+    /// The movie was recorded with a trap handler for undefined opcodes, but the machine has
+    /// none.
+    TrapRequired,
+    /// The movie was recorded with another ROM.
+    RomMismatch {
+        expected: u64,
+        found: u64,
+    },
+    Load(LoadError),
+    /// The screen at the end of playback differs from the recording.
+    Desync {
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl std::fmt::Display for MovieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "Not a movie file"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported movie version {version}")
+            }
+            Self::Truncated => write!(f, "Movie file is truncated"),
+            Self::Corrupt => write!(f, "Movie file is corrupt"),
+            Self::TrapRequired => write!(
+                f,
+                "Movie was recorded with a trap handler for undefined opcodes"
+            ),
+            Self::RomMismatch { expected, found } => write!(
+                f,
+                "Movie was recorded with another ROM (expected hash {expected:016x}, found {found:016x})"
+            ),
+            Self::Load(err) => write!(f, "{err}"),
+            Self::Desync { expected, found } => write!(
+                f,
+                "Playback desynced: final screen hash is {found:016x}, expected {expected:016x}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws the 0 digit at a random position, clearing the screen while key 0 is held
+    const ROM: [u8; 14] = [
+        0xC0, 0x3F, 0xC1, 0x1F, 0xF2, 0x29, 0xD0, 0x15, 0xE3, 0xA1, 0x00, 0xE0, 0x12, 0x00,
+    ];
+
+    fn record(chip8: &mut Chip8) -> Movie {
+        let mut movie = Movie::record(chip8, &ROM, 7, 10).unwrap();
+        for frame in 0..20 {
+            chip8.set_key(0, frame % 3 == 0);
+            chip8.run_frame(10).unwrap();
+            movie.record_frame(chip8);
+        }
+        movie
+    }
+
+    fn play(movie: &Movie, chip8: &mut Chip8) {
+        movie.start(chip8, &ROM).unwrap();
+        let mut frame = 0;
+        while movie.apply_frame(chip8, frame) {
+            chip8.run_frame(movie.instructions_per_frame()).unwrap();
+            frame += 1;
+        }
+    }
+
+    #[test]
+    fn playback() {
+        let movie = record(&mut Chip8::new());
+        let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+        assert_eq!(movie.len(), 20);
+
+        let mut chip8 = Chip8::new();
+        play(&movie, &mut chip8);
+        movie.verify(&chip8).unwrap();
+    }
+
+    #[test]
+    fn desync() {
+        let mut movie = record(&mut Chip8::new());
+        movie.frames[19] ^= 1;
+
+        let mut chip8 = Chip8::new();
+        play(&movie, &mut chip8);
+        assert!(matches!(
+            movie.verify(&chip8),
+            Err(MovieError::Desync { .. })
+        ));
+    }
+
+    #[test]
+    fn clears_rpl_flags() {
+        // Draws the digit held in the first RPL flag
+        let rom = [0xF0, 0x85, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06];
+
+        let mut chip8 = Chip8::new();
+        chip8.rpl_flags[0] = 3;
+        let mut movie = Movie::record(&mut chip8, &rom, 0, 10).unwrap();
+        chip8.run_frame(10).unwrap();
+        movie.record_frame(&chip8);
+
+        let mut other = Chip8::new();
+        other.rpl_flags[0] = 1;
+        movie.start(&mut other, &rom).unwrap();
+        movie.apply_frame(&mut other, 0);
+        other.run_frame(10).unwrap();
+        movie.verify(&other).unwrap();
+    }
+
+    #[test]
+    fn undefined_policy() {
+        let mut chip8 = Chip8::new();
+        chip8.set_undefined_policy(UndefinedPolicy::Ignore);
+        let movie = Movie::from_bytes(&record(&mut chip8).to_bytes()).unwrap();
+
+        let mut other = Chip8::new();
+        play(&movie, &mut other);
+        assert!(matches!(other.undefined_policy(), UndefinedPolicy::Ignore));
+
+        chip8.set_undefined_policy(UndefinedPolicy::trap(|_, _| Ok(())));
+        let movie = record(&mut chip8);
+        assert!(matches!(
+            movie.start(&mut other, &ROM),
+            Err(MovieError::TrapRequired)
+        ));
+    }
+}
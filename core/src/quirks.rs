@@ -0,0 +1,388 @@
+use crate::MEMORY_SIZE;
+
+const LEGACY_MEMORY_SIZE: usize = 0x1000;
+
+/// Behavioural differences between CHIP-8 implementations.
+///
+/// Start from [`Quirks::new`] or a [`Platform`] profile and adjust individual flags with the
+/// `with_*` builder methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// The AND, OR and XOR opcodes (`8xy1`, `8xy2` and `8xy3`) reset the flags register to zero.
+    vf_reset: bool,
+    /// The save and load opcodes (`Fx55` and `Fx65`) increment the index register.
+    memory: bool,
+    /// Sprites drawn at the bottom edge of the screen get clipped instead of wrapping around the screen.
+    clipping: bool,
+    /// The shift opcodes (`8xy6` and `8xyE`) only operate on vX instead of storing the shifted version of vY in vX.
+    shifting: bool,
+    /// The jump instruction (`Bnnn`) doesn't use v0, but vX instead where X is the highest nibble of nnn.
+    jumping: bool,
+    /// The get key instruction (`Fx0A`) waits for a key press and key up.
+    release: bool,
+    /// The draw instruction (`Dxyn`) waits for the vertical blank in low resolution, so execution resumes at the start of the next frame.
+    display_wait: bool,
+    /// The machine has 64 KiB of memory like XO-CHIP instead of the original 4 KiB.
+    extended_memory: bool,
+    /// Memory addresses, key numbers and font digits are masked to their bus width like on real hardware instead of raising an error when out of range.
+    wrap_around: bool,
+    /// The draw instruction `Dxy0` draws a 16 rows tall sprite like SUPER-CHIP instead of nothing.
+    large_sprites: bool,
+    /// Large sprites are 16 pixels wide in low resolution too instead of 8, like in high resolution.
+    wide_lores_sprites: bool,
+}
+
+impl Quirks {
+    /// Returns the quirks of the original COSMAC VIP interpreter, except for the display wait,
+    /// which needs the timers to be ticked every frame. [`Platform::CosmacVip`] turns it on.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            vf_reset: true,
+            memory: true,
+            clipping: true,
+            shifting: false,
+            jumping: false,
+            release: true,
+            display_wait: false,
+            extended_memory: false,
+            wrap_around: false,
+            large_sprites: false,
+            wide_lores_sprites: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_vf_reset(mut self, enabled: bool) -> Self {
+        self.vf_reset = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_memory(mut self, enabled: bool) -> Self {
+        self.memory = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_clipping(mut self, enabled: bool) -> Self {
+        self.clipping = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_shifting(mut self, enabled: bool) -> Self {
+        self.shifting = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_jumping(mut self, enabled: bool) -> Self {
+        self.jumping = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_release(mut self, enabled: bool) -> Self {
+        self.release = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_display_wait(mut self, enabled: bool) -> Self {
+        self.display_wait = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_extended_memory(mut self, enabled: bool) -> Self {
+        self.extended_memory = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_wrap_around(mut self, enabled: bool) -> Self {
+        self.wrap_around = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_large_sprites(mut self, enabled: bool) -> Self {
+        self.large_sprites = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_wide_lores_sprites(mut self, enabled: bool) -> Self {
+        self.wide_lores_sprites = enabled;
+        self
+    }
+
+    #[must_use]
+    pub const fn vf_reset(&self) -> bool {
+        self.vf_reset
+    }
+
+    #[must_use]
+    pub const fn memory(&self) -> bool {
+        self.memory
+    }
+
+    #[must_use]
+    pub const fn clipping(&self) -> bool {
+        self.clipping
+    }
+
+    #[must_use]
+    pub const fn shifting(&self) -> bool {
+        self.shifting
+    }
+
+    #[must_use]
+    pub const fn jumping(&self) -> bool {
+        self.jumping
+    }
+
+    #[must_use]
+    pub const fn release(&self) -> bool {
+        self.release
+    }
+
+    #[must_use]
+    pub const fn display_wait(&self) -> bool {
+        self.display_wait
+    }
+
+    #[must_use]
+    pub const fn extended_memory(&self) -> bool {
+        self.extended_memory
+    }
+
+    #[must_use]
+    pub const fn wrap_around(&self) -> bool {
+        self.wrap_around
+    }
+
+    #[must_use]
+    pub const fn large_sprites(&self) -> bool {
+        self.large_sprites
+    }
+
+    #[must_use]
+    pub const fn wide_lores_sprites(&self) -> bool {
+        self.wide_lores_sprites
+    }
+
+    /// Returns the size of the address space in bytes.
+    #[must_use]
+    pub const fn memory_size(&self) -> usize {
+        if self.extended_memory {
+            MEMORY_SIZE
+        } else {
+            LEGACY_MEMORY_SIZE
+        }
+    }
+
+    pub(crate) const fn to_bits(self) -> u16 {
+        (self.vf_reset as u16)
+            | (self.memory as u16) << 1
+            | (self.clipping as u16) << 2
+            | (self.shifting as u16) << 3
+            | (self.jumping as u16) << 4
+            | (self.release as u16) << 5
+            | (self.display_wait as u16) << 6
+            | (self.extended_memory as u16) << 7
+            | (self.wrap_around as u16) << 8
+            | (self.large_sprites as u16) << 9
+            | (self.wide_lores_sprites as u16) << 10
+    }
+
+    pub(crate) const fn from_bits(bits: u16) -> Self {
+        Self {
+            vf_reset: bits & 0x01 != 0,
+            memory: bits & 0x02 != 0,
+            clipping: bits & 0x04 != 0,
+            shifting: bits & 0x08 != 0,
+            jumping: bits & 0x10 != 0,
+            release: bits & 0x20 != 0,
+            display_wait: bits & 0x40 != 0,
+            extended_memory: bits & 0x80 != 0,
+            wrap_around: bits & 0x100 != 0,
+            large_sprites: bits & 0x200 != 0,
+            wide_lores_sprites: bits & 0x400 != 0,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Platform> for Quirks {
+    fn from(platform: Platform) -> Self {
+        platform.quirks()
+    }
+}
+
+/// Named quirk profiles for the common CHIP-8 implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// The original interpreter on the RCA COSMAC VIP.
+    CosmacVip,
+    /// CHIP-48 on the HP 48 calculators.
+    Chip48,
+    /// SUPER-CHIP 1.0 on the HP 48 calculators.
+    SuperChip10,
+    /// SUPER-CHIP 1.1 on the HP 48 calculators.
+    SuperChip11,
+    /// SUPER-CHIP as implemented by modern interpreters such as Octo.
+    SuperChipModern,
+    /// XO-CHIP as defined by Octo.
+    XoChip,
+}
+
+impl Platform {
+    pub const ALL: [Self; 6] = [
+        Self::CosmacVip,
+        Self::Chip48,
+        Self::SuperChip10,
+        Self::SuperChip11,
+        Self::SuperChipModern,
+        Self::XoChip,
+    ];
+
+    /// Returns a short lowercase identifier suitable for command lines and config files.
+    #[must_use]
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::CosmacVip => "vip",
+            Self::Chip48 => "chip48",
+            Self::SuperChip10 => "schip10",
+            Self::SuperChip11 => "schip11",
+            Self::SuperChipModern => "schip",
+            Self::XoChip => "xochip",
+        }
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::CosmacVip => "COSMAC VIP",
+            Self::Chip48 => "CHIP-48",
+            Self::SuperChip10 => "SCHIP 1.0",
+            Self::SuperChip11 => "SCHIP 1.1",
+            Self::SuperChipModern => "Modern SCHIP",
+            Self::XoChip => "XO-CHIP",
+        }
+    }
+
+    /// CHIP-48 and SCHIP 1.0 increment the index register by X rather than X + 1 on save and
+    /// load; both are mapped to the incrementing behaviour. SCHIP 1.0 and 1.1 draw `Dxy0` as an
+    /// 8 by 16 sprite in low resolution. SCHIP 1.1 also scrolls by half a
+    /// pixel in low resolution and sets VF to the number of colliding rows in high resolution,
+    /// neither of which is modelled.
+    #[must_use]
+    pub const fn quirks(self) -> Quirks {
+        match self {
+            Self::CosmacVip => Quirks::new().with_display_wait(true),
+            Self::Chip48 => Quirks::new()
+                .with_vf_reset(false)
+                .with_shifting(true)
+                .with_jumping(true),
+            Self::SuperChip10 => Quirks::new()
+                .with_vf_reset(false)
+                .with_shifting(true)
+                .with_jumping(true)
+                .with_large_sprites(true),
+            Self::SuperChip11 => Quirks::new()
+                .with_vf_reset(false)
+                .with_display_wait(true)
+                .with_memory(false)
+                .with_shifting(true)
+                .with_jumping(true)
+                .with_large_sprites(true),
+            Self::SuperChipModern => Quirks::new()
+                .with_vf_reset(false)
+                .with_memory(false)
+                .with_shifting(true)
+                .with_jumping(true)
+                .with_large_sprites(true)
+                .with_wide_lores_sprites(true),
+            Self::XoChip => Quirks::new()
+                .with_vf_reset(false)
+                .with_clipping(false)
+                .with_extended_memory(true)
+                .with_large_sprites(true)
+                .with_wide_lores_sprites(true),
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Platform {
+    type Err = ParsePlatformError;
+
+    /// Parses either the [`Platform::id`] or the [`Platform::name`], ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|platform| {
+                platform.id().eq_ignore_ascii_case(s) || platform.name().eq_ignore_ascii_case(s)
+            })
+            .ok_or_else(|| ParsePlatformError(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsePlatformError(String);
+
+impl std::fmt::Display for ParsePlatformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Unknown platform {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePlatformError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platforms() {
+        for platform in Platform::ALL {
+            assert_eq!(platform.id().parse::<Platform>().unwrap(), platform);
+            let quirks = platform.quirks();
+            assert_eq!(Quirks::from_bits(quirks.to_bits()), quirks);
+        }
+
+        // Every profile is distinct
+        let bits: std::collections::BTreeSet<_> = Platform::ALL
+            .iter()
+            .map(|platform| platform.quirks().to_bits())
+            .collect();
+        assert_eq!(bits.len(), Platform::ALL.len());
+        assert!("chip9".parse::<Platform>().is_err());
+    }
+
+    #[test]
+    fn profiles() {
+        let vip = Platform::CosmacVip.quirks();
+        assert!(vip.vf_reset() && vip.memory() && vip.display_wait());
+        assert_eq!(vip.memory_size(), 0x1000);
+
+        let schip = Platform::SuperChipModern.quirks();
+        assert!(schip.shifting() && schip.jumping() && !schip.memory());
+
+        let xo = Platform::XoChip.quirks();
+        assert!(!xo.clipping() && !xo.shifting());
+        assert_eq!(xo.memory_size(), 0x10000);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::Chip8;
+use std::collections::VecDeque;
+
+/// Keeps recent snapshots of a [`Chip8`] so that it can be played backwards.
+///
+/// A snapshot is taken every `interval` frames. Only the newest one is stored whole; each older
+/// one is stored as the run-length encoded XOR of itself and the next newer snapshot, which is
+/// mostly zeros since little of the machine changes between frames. The oldest snapshots are
+/// dropped to stay within the memory budget.
+#[derive(Debug, Clone)]
+pub struct RewindBuffer {
+    interval: u32,
+    budget: usize,
+    // The number of frames recorded, rewinding moves it back
+    frame: u64,
+    latest: Option<Snapshot>,
+    // Oldest first, each one turns the state of the next newer snapshot into its own
+    deltas: VecDeque<Snapshot>,
+    size: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    frame: u64,
+    cycles: u64,
+    data: Vec<u8>,
+}
+
+impl RewindBuffer {
+    /// Creates a buffer that takes a snapshot every `interval` frames and keeps at most about
+    /// `budget` bytes of them. An interval of 0 is treated as 1.
+    #[must_use]
+    pub fn new(interval: u32, budget: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            budget,
+            frame: 0,
+            latest: None,
+            deltas: VecDeque::new(),
+            size: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    #[must_use]
+    pub const fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the number of bytes used by the snapshots.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of snapshots kept.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.deltas.len() + usize::from(self.latest.is_some())
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Returns how many frames back the oldest snapshot is.
+    #[must_use]
+    pub fn depth(&self) -> u64 {
+        let oldest = self.deltas.front().or(self.latest.as_ref());
+        oldest.map_or(0, |snapshot| self.frame - snapshot.frame)
+    }
+
+    /// Drops every snapshot, for example when another ROM is loaded.
+    pub fn clear(&mut self) {
+        self.frame = 0;
+        self.latest = None;
+        self.deltas.clear();
+        self.size = 0;
+    }
+
+    /// Counts a frame and takes a snapshot when the interval is reached. Call it once per
+    /// frame, after the timers are ticked.
+    pub fn record_frame(&mut self, chip8: &Chip8) {
+        self.frame += 1;
+        let due = self
+            .latest
+            .as_ref()
+            .is_none_or(|latest| self.frame - latest.frame >= self.interval as u64);
+        if due {
+            self.push(chip8);
+        }
+    }
+
+    /// Moves the machine back by at least `frames` frames, to the newest snapshot taken that
+    /// long ago, or to the oldest one when the buffer doesn't reach back that far.
+    ///
+    /// Returns the number of frames actually rewound, 0 when the buffer is empty. The keypad is
+    /// left as it is, since it follows the input of the host.
+    pub fn rewind(&mut self, chip8: &mut Chip8, frames: u64) -> u64 {
+        let frame = self.frame;
+        let target = frame.saturating_sub(frames);
+        if self.restore(chip8, |snapshot| snapshot.frame <= target) {
+            frame - self.frame
+        } else {
+            0
+        }
+    }
+
+    /// Moves the machine back to the newest snapshot taken after at most `cycles` executed
+    /// instructions, as counted by [`Chip8::cycles`].
+    ///
+    /// Returns `false` and leaves the machine untouched when no snapshot is that old.
+    pub fn rewind_to_cycle(&mut self, chip8: &mut Chip8, cycles: u64) -> bool {
+        let reachable = self
+            .deltas
+            .front()
+            .or(self.latest.as_ref())
+            .is_some_and(|oldest| oldest.cycles <= cycles);
+        reachable && self.restore(chip8, |snapshot| snapshot.cycles <= cycles)
+    }
+
+    fn push(&mut self, chip8: &Chip8) {
+        let data = chip8.save_state();
+        if let Some(latest) = self.latest.take() {
+            if latest.data.len() == data.len() {
+                let delta = encode(&latest.data, &data);
+                self.size = self.size - latest.data.len() + delta.len();
+                self.deltas.push_back(Snapshot {
+                    data: delta,
+                    ..latest
+                });
+            } else {
+                self.deltas.clear();
+                self.size = 0;
+            }
+        }
+
+        self.size += data.len();
+        self.latest = Some(Snapshot {
+            frame: self.frame,
+            cycles: chip8.cycles(),
+            data,
+        });
+
+        while self.size > self.budget
+            && let Some(oldest) = self.deltas.pop_front()
+        {
+            self.size -= oldest.data.len();
+        }
+    }
+
+    /// Drops the snapshots newer than the newest one accepted by `keep`, or all but the oldest
+    /// when none is, and loads the remaining newest one into the machine. Returns whether a
+    /// snapshot was loaded.
+    fn restore(&mut self, chip8: &mut Chip8, keep: impl Fn(&Snapshot) -> bool) -> bool {
+        let Some(latest) = &mut self.latest else {
+            return false;
+        };
+
+        while !keep(latest)
+            && let Some(delta) = self.deltas.pop_back()
+        {
+            apply(&delta.data, &mut latest.data);
+            latest.frame = delta.frame;
+            latest.cycles = delta.cycles;
+            self.size -= delta.data.len();
+        }
+
+        let keys = *chip8.keys();
+        if chip8.load_state(&latest.data).is_err() {
+            // The snapshots belong to another ROM
+            self.clear();
+            return false;
+        }
+        for (key, &pressed) in keys.iter().enumerate() {
+            chip8.set_key(key, pressed);
+        }
+
+        self.frame = latest.frame;
+        true
+    }
+}
+
+/// Encodes `old ^ new` as runs of unchanged bytes followed by runs of XORed bytes, with both
+/// lengths written as LEB128.
+fn encode(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    let mut i = 0;
+
+    while i < old.len() {
+        let start = i;
+        while i < old.len() && old[i] == new[i] {
+            i += 1;
+        }
+        let unchanged = i - start;
+        if i == old.len() {
+            break;
+        }
+
+        let start = i;
+        // A single unchanged byte is cheaper to keep inside the run than to split it
+        while i < old.len() && (old[i] != new[i] || old.get(i + 1) != new.get(i + 1)) {
+            i += 1;
+        }
+
+        write_len(&mut delta, unchanged);
+        write_len(&mut delta, i - start);
+        delta.extend(old[start..i].iter().zip(&new[start..i]).map(|(a, b)| a ^ b));
+    }
+
+    delta
+}
+
+/// XORs an encoded delta into `data`.
+fn apply(delta: &[u8], data: &mut [u8]) {
+    let mut delta = delta.iter().copied();
+    let mut pos = 0;
+
+    while let Some(unchanged) = read_len(&mut delta) {
+        pos += unchanged;
+        let len = read_len(&mut delta).unwrap_or_default();
+        for (byte, mask) in data[pos..pos + len].iter_mut().zip(&mut delta) {
+            *byte ^= mask;
+        }
+        pos += len;
+    }
+}
+
+fn write_len(data: &mut Vec<u8>, mut len: usize) {
+    while len >= 0x80 {
+        data.push((len as u8 & 0x7F) | 0x80);
+        len >>= 7;
+    }
+    data.push(len as u8);
+}
+
+fn read_len(data: &mut impl Iterator<Item = u8>) -> Option<usize> {
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data.next()?;
+        len |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(len);
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts frames in V0 and loops
+    const ROM: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+    fn run(rewind: &mut RewindBuffer, chip8: &mut Chip8, frames: u32) {
+        for _ in 0..frames {
+            chip8.run_frame(2).unwrap();
+            rewind.record_frame(chip8);
+        }
+    }
+
+    #[test]
+    fn encode_and_apply() {
+        let old: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut new = old.clone();
+        new[0] ^= 1;
+        new[2] ^= 1;
+        new[300..500].fill(0xAA);
+        new[999] = 0;
+
+        assert!(encode(&old, &old).is_empty());
+
+        let delta = encode(&old, &new);
+        assert!(delta.len() < 250);
+        let mut data = old.clone();
+        apply(&delta, &mut data);
+        assert_eq!(data, new);
+        apply(&delta, &mut data);
+        assert_eq!(data, old);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut chip8 = Chip8::new();
+        chip8.load(&ROM).unwrap();
+        let mut rewind = RewindBuffer::new(2, usize::MAX);
+        run(&mut rewind, &mut chip8, 10);
+        assert_eq!(rewind.len(), 5);
+        assert_eq!(rewind.depth(), 9);
+
+        // Back to the snapshot of frame 7
+        assert_eq!(rewind.rewind(&mut chip8, 2), 3);
+        assert_eq!(chip8.register(0), 7);
+        assert_eq!(rewind.len(), 4);
+
+        assert!(rewind.rewind_to_cycle(&mut chip8, 6));
+        assert_eq!(chip8.cycles(), 6);
+        assert_eq!(chip8.register(0), 3);
+        assert!(!rewind.rewind_to_cycle(&mut chip8, 0));
+
+        // Only the oldest snapshot is left
+        assert_eq!(rewind.rewind(&mut chip8, 100), 2);
+        assert_eq!(chip8.register(0), 1);
+        assert_eq!(rewind.rewind(&mut chip8, 100), 0);
+    }
+
+    #[test]
+    fn budget() {
+        let mut chip8 = Chip8::new();
+        chip8.load(&ROM).unwrap();
+        let budget = chip8.save_state().len() + 200;
+        let mut rewind = RewindBuffer::new(1, budget);
+        run(&mut rewind, &mut chip8, 100);
+
+        assert!(rewind.size() <= budget);
+        assert!(rewind.len() > 1);
+        let depth = rewind.depth();
+        assert!(depth < 99);
+
+        assert_eq!(rewind.rewind(&mut chip8, 100), depth);
+        assert_eq!(chip8.register(0) as u64, 100 - depth);
+    }
+}
@@ -0,0 +1,136 @@
+use rand::Rng;
+
+/// Random number source for `Cxkk`.
+///
+/// By default a SplitMix64 generator is used so the state can be seeded, saved and restored.
+/// A custom generator replaces it entirely.
+pub(crate) struct Random {
+    seed: u64,
+    state: u64,
+    // Whether the seed was chosen by the caller rather than drawn at random
+    seeded: bool,
+    custom: Option<Box<dyn Rng + Send>>,
+}
+
+impl Random {
+    pub(crate) fn new() -> Self {
+        Self {
+            seeded: false,
+            ..Self::from_seed(rand::random())
+        }
+    }
+
+    pub(crate) const fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            state: seed,
+            seeded: true,
+            custom: None,
+        }
+    }
+
+    pub(crate) fn from_rng(rng: impl Rng + Send + 'static) -> Self {
+        Self {
+            seed: 0,
+            state: 0,
+            seeded: false,
+            custom: Some(Box::new(rng)),
+        }
+    }
+
+    /// Returns the seed, or `None` when a custom generator is used.
+    pub(crate) const fn seed(&self) -> Option<u64> {
+        if self.custom.is_some() {
+            None
+        } else {
+            Some(self.seed)
+        }
+    }
+
+    /// Returns the seed and current state of the built-in generator, or `None` when a custom
+    /// generator is used.
+    pub(crate) const fn state(&self) -> Option<(u64, u64)> {
+        if self.custom.is_some() {
+            None
+        } else {
+            Some((self.seed, self.state))
+        }
+    }
+
+    pub(crate) fn restore(&mut self, seed: u64, state: u64) {
+        self.seed = seed;
+        self.state = state;
+        self.custom = None;
+    }
+
+    /// Rewinds the built-in generator to the seed chosen by the caller, or draws a new random
+    /// seed when none was chosen.
+    pub(crate) fn restart(&mut self) {
+        if !self.seeded {
+            self.seed = rand::random();
+        }
+        self.state = self.seed;
+    }
+
+    pub(crate) fn next_byte(&mut self) -> u8 {
+        if let Some(rng) = &mut self.custom {
+            return rng.next_u32() as u8;
+        }
+
+        // SplitMix64
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        (z ^ (z >> 31)) as u8
+    }
+}
+
+impl std::fmt::Debug for Random {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Random")
+            .field("seed", &self.seed)
+            .field("state", &self.state)
+            .field("seeded", &self.seeded)
+            .field("custom", &self.custom.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Chip8;
+
+    // Fills v0 to vF with random bytes
+    fn random_registers(chip8: &mut Chip8) -> [u8; 16] {
+        let rom: Vec<u8> = (0..16).flat_map(|x| [0xC0 | x, 0xFF]).collect();
+        chip8.load(&rom).unwrap();
+        for _ in 0..16 {
+            chip8.emulate().unwrap();
+        }
+        std::array::from_fn(|x| chip8.register(x))
+    }
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let mut chip8 = Chip8::new();
+        chip8.set_seed(42);
+        let first = random_registers(&mut chip8);
+
+        let mut other = Chip8::new();
+        other.set_seed(42);
+        assert_eq!(random_registers(&mut other), first);
+
+        chip8.reset();
+        assert_eq!(random_registers(&mut chip8), first);
+        assert_eq!(chip8.seed(), Some(42));
+    }
+
+    #[test]
+    fn unseeded_reset_draws_new_seed() {
+        let mut chip8 = Chip8::new();
+        let first = random_registers(&mut chip8);
+        chip8.reset();
+        assert_ne!(random_registers(&mut chip8), first);
+    }
+}
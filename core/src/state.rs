@@ -0,0 +1,286 @@
+use crate::{
+    AUDIO_PATTERN_SIZE, Chip8, HIRES_HEIGHT, HIRES_WIDTH, KEY_COUNT, MEMORY_SIZE, Quirks,
+    REGISTER_COUNT, RPL_FLAG_COUNT, STACK_SIZE,
+};
+
+const MAGIC: [u8; 4] = *b"C8SS";
+const FORMAT_VERSION: u16 = 1;
+const NO_PRESSED_KEY: u8 = 0xFF;
+
+impl Chip8 {
+    /// Serializes the complete machine state.
+    ///
+    /// The state starts with a header holding the format version and the hash of the loaded
+    /// ROM, followed by a fixed-size little-endian dump of the machine.
+    #[must_use]
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(MEMORY_SIZE + HIRES_WIDTH * HIRES_HEIGHT + 128);
+
+        data.extend_from_slice(&MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(&self.rom_hash.to_le_bytes());
+
+        data.extend_from_slice(&self.memory);
+        data.extend_from_slice(&self.registers);
+        data.extend_from_slice(&self.index.to_le_bytes());
+        data.extend_from_slice(&self.pc.to_le_bytes());
+        data.push(self.sp);
+        for addr in self.stack {
+            data.extend_from_slice(&addr.to_le_bytes());
+        }
+        data.push(self.delay_timer);
+        data.push(self.sound_timer);
+        data.extend(self.keys.map(u8::from));
+        data.extend_from_slice(&self.framebuffer);
+        data.push(self.hires as u8);
+        data.push(self.planes);
+        data.extend_from_slice(&self.audio_pattern);
+        data.push(self.pitch);
+        data.push(self.halted as u8);
+        data.extend_from_slice(&self.rpl_flags);
+        data.extend_from_slice(&self.quirks.to_bits().to_le_bytes());
+        data.push(self.pressed_key.map_or(NO_PRESSED_KEY, |key| key as u8));
+        let rng = self.rng.state();
+        let (seed, rng_state) = rng.unwrap_or_default();
+        data.push(rng.is_some() as u8);
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(&rng_state.to_le_bytes());
+        data.extend_from_slice(&self.cycles.to_le_bytes());
+        data.extend_from_slice(&self.frame_cycles.to_le_bytes());
+        data.push(self.buzzer_active as u8);
+        data.push(self.display_waiting as u8);
+
+        data
+    }
+
+    /// Restores a state created by [`Chip8::save_state`].
+    ///
+    /// The state must have been saved while the same ROM was loaded. The machine is left
+    /// untouched when an error is returned.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = Reader { data };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+
+        let version = reader.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let rom_hash = reader.u64()?;
+        if rom_hash != self.rom_hash {
+            return Err(StateError::RomMismatch {
+                expected: self.rom_hash,
+                found: rom_hash,
+            });
+        }
+
+        let memory = reader.array::<MEMORY_SIZE>()?;
+        let registers = reader.array::<REGISTER_COUNT>()?;
+        let index = reader.u16()?;
+        let pc = reader.u16()?;
+        let sp = reader.u8()?;
+        let mut stack = [0; STACK_SIZE];
+        for addr in &mut stack {
+            *addr = reader.u16()?;
+        }
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let keys = reader.array::<KEY_COUNT>()?;
+        let framebuffer = reader.array::<{ HIRES_WIDTH * HIRES_HEIGHT }>()?;
+        let hires = reader.bool()?;
+        let planes = reader.u8()?;
+        let audio_pattern = reader.array::<AUDIO_PATTERN_SIZE>()?;
+        let pitch = reader.u8()?;
+        let halted = reader.bool()?;
+        let rpl_flags = reader.array::<RPL_FLAG_COUNT>()?;
+        let quirks = Quirks::from_bits(reader.u16()?);
+        let pressed_key = match reader.u8()? {
+            NO_PRESSED_KEY => None,
+            key if (key as usize) < KEY_COUNT => Some(key as usize),
+            _ => return Err(StateError::Corrupt),
+        };
+        let has_rng_state = reader.bool()?;
+        let seed = reader.u64()?;
+        let rng_state = reader.u64()?;
+        let cycles = reader.u64()?;
+        let frame_cycles = reader.u32()?;
+        let buzzer_active = reader.bool()?;
+        let display_waiting = reader.bool()?;
+
+        if !reader.data.is_empty() || sp as usize > STACK_SIZE || keys.iter().any(|&key| key > 1) {
+            return Err(StateError::Corrupt);
+        }
+
+        self.memory = memory;
+        self.registers = registers;
+        self.index = index;
+        self.pc = pc;
+        self.sp = sp;
+        self.stack = stack;
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.keys = keys.map(|key| key == 1);
+        self.framebuffer = framebuffer;
+        self.hires = hires;
+        self.planes = planes;
+        self.audio_pattern = audio_pattern;
+        self.pitch = pitch;
+        self.halted = halted;
+        self.rpl_flags = rpl_flags;
+        self.quirks = quirks;
+        self.pressed_key = pressed_key;
+        self.cycles = cycles;
+        self.frame_cycles = frame_cycles;
+        self.buzzer_active = buzzer_active;
+        self.display_waiting = display_waiting;
+        // A custom generator is kept when the state was saved without one
+        if has_rng_state {
+            self.rng.restore(seed, rng_state);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the hash identifying the loaded ROM, as stored in save states.
+    #[must_use]
+    pub const fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and compiler versions.
+pub(crate) fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupt),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    RomMismatch { expected: u64, found: u64 },
+    Truncated,
+    Corrupt,
+}
+
+impl std::fmt::Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "Not a save state"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported save state version {version}")
+            }
+            Self::RomMismatch { expected, found } => write!(
+                f,
+                "Save state belongs to ROM {found:016x}, but ROM {expected:016x} is loaded"
+            ),
+            Self::Truncated => write!(f, "Save state is truncated"),
+            Self::Corrupt => write!(f, "Save state is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Platform;
+
+    // Draws the 0 digit at a random position, clearing the screen while key 0 is held
+    const ROM: [u8; 14] = [
+        0xC0, 0x3F, 0xC1, 0x1F, 0xF2, 0x29, 0xD0, 0x15, 0xE3, 0xA1, 0x00, 0xE0, 0x12, 0x00,
+    ];
+
+    fn run_frames(chip8: &mut Chip8, frames: usize) {
+        for _ in 0..frames {
+            chip8.run_frame(10).unwrap();
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut chip8 = Chip8::with_quirks(Platform::XoChip.quirks());
+        chip8.set_seed(7);
+        chip8.load(&ROM).unwrap();
+        run_frames(&mut chip8, 3);
+        let state = chip8.save_state();
+        run_frames(&mut chip8, 5);
+
+        let mut restored = Chip8::new();
+        restored.load(&ROM).unwrap();
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.quirks(), Platform::XoChip.quirks());
+        assert_eq!(restored.save_state(), state);
+
+        run_frames(&mut restored, 5);
+        assert_eq!(restored.framebuffer(), chip8.framebuffer());
+    }
+
+    #[test]
+    fn rejects_invalid_states() {
+        let mut chip8 = Chip8::new();
+        chip8.load(&ROM).unwrap();
+        let state = chip8.save_state();
+
+        let mut other = Chip8::new();
+        other.load(&ROM[..2]).unwrap();
+        assert!(matches!(
+            other.load_state(&state),
+            Err(StateError::RomMismatch { .. })
+        ));
+        assert!(matches!(
+            chip8.load_state(&state[..state.len() - 1]),
+            Err(StateError::Truncated)
+        ));
+    }
+}
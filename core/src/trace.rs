@@ -0,0 +1,438 @@
+use crate::{Chip8, Instruction, Registers, Syntax};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// The machine state just before an instruction executes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The number of instructions executed before this one since the last reset.
+    pub cycle: u64,
+    pub opcode: u16,
+    /// The registers, with `pc` holding the address of the instruction.
+    pub registers: Registers,
+}
+
+impl TraceEntry {
+    #[must_use]
+    pub const fn instruction(&self) -> Instruction {
+        Instruction::decode(self.opcode)
+    }
+}
+
+/// Prints the entry as one line of a trace log, such as
+/// `00000042 PC:0206 OP:7001 V0:00 ... VF:00 I:0000 SP:0 DT:00 ST:00 ADD V0, 0x01`.
+///
+/// This layout is this crate's own. Use a [`TraceFormat`] to match the log of the emulator
+/// being compared against.
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let registers = &self.registers;
+        write!(
+            f,
+            "{:08} PC:{:04X} OP:{:04X}",
+            self.cycle, registers.pc, self.opcode
+        )?;
+        for (x, value) in registers.v.iter().enumerate() {
+            write!(f, " V{x:X}:{value:02X}")?;
+        }
+        write!(
+            f,
+            " I:{:04X} SP:{:X} DT:{:02X} ST:{:02X} {}",
+            registers.index,
+            registers.sp,
+            registers.delay_timer,
+            registers.sound_timer,
+            self.instruction()
+        )
+    }
+}
+
+/// The layout of a trace line, parsed from a template such as `{PC}: {OP} {mnemonic}`.
+///
+/// Placeholders are replaced by the fields of the entry and everything else is copied as is,
+/// with `{{` and `}}` standing for braces:
+///
+/// - `{cycle}`: the decimal cycle count
+/// - `{pc}`, `{op}` and `{i}`: four hex digits
+/// - `{v0}` to `{vf}`, `{dt}` and `{st}`: two hex digits
+/// - `{sp}`: one hex digit
+/// - `{mnemonic}` and `{octo}`: the instruction in Cowgod or Octo syntax
+///
+/// Hex digits are uppercase when the placeholder name is, so `{PC}` prints `020A` and `{pc}`
+/// prints `020a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceFormat {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field { field: Field, uppercase: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Cycle,
+    Pc,
+    Opcode,
+    Register(usize),
+    Index,
+    Sp,
+    DelayTimer,
+    SoundTimer,
+    Mnemonic(Syntax),
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name.to_ascii_lowercase().as_str() {
+            "cycle" => Self::Cycle,
+            "pc" => Self::Pc,
+            "op" => Self::Opcode,
+            "i" => Self::Index,
+            "sp" => Self::Sp,
+            "dt" => Self::DelayTimer,
+            "st" => Self::SoundTimer,
+            "mnemonic" => Self::Mnemonic(Syntax::Cowgod),
+            "octo" => Self::Mnemonic(Syntax::Octo),
+            name => {
+                let digit = name.strip_prefix('v').filter(|digit| digit.len() == 1)?;
+                Self::Register(usize::from_str_radix(digit, 16).ok()?)
+            }
+        };
+        Some(field)
+    }
+}
+
+impl TraceFormat {
+    /// Prints `entry` in this format, without a line ending.
+    #[must_use]
+    pub fn display<'a>(&'a self, entry: &'a TraceEntry) -> impl fmt::Display + 'a {
+        FormattedEntry {
+            format: self,
+            entry,
+        }
+    }
+}
+
+struct FormattedEntry<'a> {
+    format: &'a TraceFormat,
+    entry: &'a TraceEntry,
+}
+
+impl fmt::Display for FormattedEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entry = self.entry;
+        let registers = &entry.registers;
+
+        for part in &self.format.parts {
+            let (field, uppercase) = match part {
+                Part::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                }
+                Part::Field { field, uppercase } => (*field, *uppercase),
+            };
+            let (value, width) = match field {
+                Field::Cycle => {
+                    write!(f, "{}", entry.cycle)?;
+                    continue;
+                }
+                Field::Mnemonic(syntax) => {
+                    write!(f, "{}", entry.instruction().mnemonic(syntax))?;
+                    continue;
+                }
+                Field::Pc => (registers.pc, 4),
+                Field::Opcode => (entry.opcode, 4),
+                Field::Index => (registers.index, 4),
+                Field::Register(x) => (registers.v[x] as u16, 2),
+                Field::DelayTimer => (registers.delay_timer as u16, 2),
+                Field::SoundTimer => (registers.sound_timer as u16, 2),
+                Field::Sp => (registers.sp as u16, 1),
+            };
+            if uppercase {
+                write!(f, "{value:0width$X}")?;
+            } else {
+                write!(f, "{value:0width$x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for TraceFormat {
+    type Err = ParseTraceFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("{{") {
+                text.push('{');
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("}}") {
+                text.push('}');
+                rest = after;
+            } else if c == '{' {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| ParseTraceFormatError(rest.to_string()))?;
+                let name = &rest[1..end];
+                let field =
+                    Field::parse(name).ok_or_else(|| ParseTraceFormatError(name.to_string()))?;
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Field {
+                    field,
+                    uppercase: name.chars().any(|c| c.is_ascii_uppercase()),
+                });
+                rest = &rest[end + 1..];
+            } else {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+/// A trace format template with an unknown or unclosed placeholder.
+#[derive(Debug)]
+pub struct ParseTraceFormatError(String);
+
+impl fmt::Display for ParseTraceFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown trace field {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseTraceFormatError {}
+
+/// Receives every instruction executed by a [`Chip8`] with a tracer attached.
+pub trait TraceSink: Send {
+    fn trace(&mut self, entry: &TraceEntry);
+}
+
+impl fmt::Debug for dyn TraceSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TraceSink")
+    }
+}
+
+/// Lets the host keep a handle on a sink after attaching it with [`Chip8::set_tracer`].
+impl<T: TraceSink> TraceSink for Arc<Mutex<T>> {
+    fn trace(&mut self, entry: &TraceEntry) {
+        // A poisoned sink only means another thread panicked while reading it
+        let mut sink = self
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        sink.trace(entry);
+    }
+}
+
+/// Writes one [`TraceEntry`] line per instruction.
+///
+/// Tracing can't fail, so the first write error is kept and later entries are dropped.
+#[derive(Debug)]
+pub struct TraceWriter<W> {
+    writer: W,
+    // `None` for the layout of `TraceEntry`'s `Display`
+    format: Option<TraceFormat>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    #[must_use]
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            format: None,
+            error: None,
+        }
+    }
+
+    /// Writes the lines in `format` instead of the default layout.
+    #[must_use]
+    pub fn with_format(mut self, format: TraceFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Flushes the writer and returns the first error met while tracing.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer.flush()
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> TraceSink for TraceWriter<W> {
+    fn trace(&mut self, entry: &TraceEntry) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = match &self.format {
+            Some(format) => writeln!(self.writer, "{}", format.display(entry)),
+            None => writeln!(self.writer, "{entry}"),
+        };
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+}
+
+/// Keeps the last `capacity` entries in memory.
+#[derive(Debug, Clone)]
+pub struct TraceBuffer {
+    entries: VecDeque<TraceEntry>,
+    capacity: usize,
+}
+
+impl TraceBuffer {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the kept entries, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl TraceSink for TraceBuffer {
+    fn trace(&mut self, entry: &TraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(*entry);
+    }
+}
+
+impl Chip8 {
+    /// Attaches a sink that receives every executed instruction, replacing any previous one.
+    ///
+    /// The tracer is kept across resets.
+    pub fn set_tracer(&mut self, tracer: impl TraceSink + 'static) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
+    /// Detaches the tracer.
+    pub fn clear_tracer(&mut self) {
+        self.tracer = None;
+    }
+
+    #[must_use]
+    pub const fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    /// Returns the number of instructions executed since the last reset.
+    #[must_use]
+    pub const fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub(crate) fn trace(&mut self, opcode: u16) {
+        if self.tracer.is_none() {
+            return;
+        }
+
+        let entry = TraceEntry {
+            cycle: self.cycles,
+            opcode,
+            registers: Registers {
+                pc: self.instruction_pc,
+                ..self.registers()
+            },
+        };
+        if let Some(tracer) = &mut self.tracer {
+            tracer.trace(&entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // v0 += 1 forever
+    const ROM: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+    fn run(chip8: &mut Chip8, cycles: usize) {
+        chip8.load(&ROM).unwrap();
+        for _ in 0..cycles {
+            chip8.emulate().unwrap();
+        }
+    }
+
+    #[test]
+    fn ring_buffer_wraps() {
+        let buffer = Arc::new(Mutex::new(TraceBuffer::new(3)));
+        let mut chip8 = Chip8::new();
+        chip8.set_tracer(Arc::clone(&buffer));
+        run(&mut chip8, 10);
+
+        let buffer = buffer.lock().unwrap();
+        let cycles: Vec<u64> = buffer.entries().map(|entry| entry.cycle).collect();
+        assert_eq!(cycles, [7, 8, 9]);
+        let last = buffer.entries().last().unwrap();
+        assert_eq!((last.registers.pc, last.opcode), (0x202, 0x1200));
+        assert_eq!(last.registers.v[0], 5);
+    }
+
+    #[test]
+    fn writer_formats() {
+        let writer = TraceWriter::new(Vec::new()).with_format(
+            "{cycle} {PC} {op} {{{vf}}} {mnemonic} / {octo}"
+                .parse()
+                .unwrap(),
+        );
+        let writer = Arc::new(Mutex::new(writer));
+        let mut chip8 = Chip8::new();
+        chip8.set_tracer(Arc::clone(&writer));
+        run(&mut chip8, 2);
+        chip8.clear_tracer();
+
+        let writer = Arc::into_inner(writer).unwrap().into_inner().unwrap();
+        let log = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            log,
+            "0 0200 7001 {00} ADD V0, 0x01 / v0 += 0x01\n1 0202 1200 {00} JP 0x200 / jump 0x200\n"
+        );
+
+        assert!("{v16}".parse::<TraceFormat>().is_err());
+        assert!("{pc".parse::<TraceFormat>().is_err());
+    }
+}
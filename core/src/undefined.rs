@@ -0,0 +1,122 @@
+use crate::{Chip8, ErrorKind};
+use std::fmt;
+
+/// Handles an undefined opcode in place of the interpreter.
+pub type TrapHandler = Box<dyn FnMut(&mut Chip8, u16) -> Result<(), ErrorKind> + Send>;
+
+/// What the interpreter does with `0nnn` machine code calls and opcodes that no variant
+/// defines.
+#[derive(Default)]
+pub enum UndefinedPolicy {
+    /// Stops with [`ErrorKind::UndefinedInstruction`].
+    #[default]
+    Halt,
+    /// Skips the opcode like a no-op.
+    Ignore,
+    /// Passes the opcode and the machine to a handler, which can emulate specific machine code
+    /// routines natively and return an error for the rest.
+    ///
+    /// The program counter already points past the opcode when the handler runs.
+    Trap(TrapHandler),
+}
+
+impl UndefinedPolicy {
+    #[must_use]
+    pub fn trap(
+        handler: impl FnMut(&mut Chip8, u16) -> Result<(), ErrorKind> + Send + 'static,
+    ) -> Self {
+        Self::Trap(Box::new(handler))
+    }
+}
+
+impl fmt::Debug for UndefinedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Halt => write!(f, "Halt"),
+            Self::Ignore => write!(f, "Ignore"),
+            Self::Trap(_) => write!(f, "Trap"),
+        }
+    }
+}
+
+impl Chip8 {
+    #[must_use]
+    pub const fn undefined_policy(&self) -> &UndefinedPolicy {
+        &self.undefined_policy
+    }
+
+    /// Sets how undefined opcodes are handled. The policy is kept across resets.
+    pub fn set_undefined_policy(&mut self, policy: UndefinedPolicy) {
+        self.undefined_policy = policy;
+    }
+
+    // 0nnn: SYS addr, and any undefined opcode
+    pub(crate) fn op_undefined(&mut self, opcode: u16) -> Result<(), ErrorKind> {
+        // The handler borrows the machine, so the policy is taken out while it runs
+        let mut policy = std::mem::take(&mut self.undefined_policy);
+        let result = match &mut policy {
+            UndefinedPolicy::Halt => Err(ErrorKind::UndefinedInstruction),
+            UndefinedPolicy::Ignore => Ok(()),
+            UndefinedPolicy::Trap(handler) => handler(self, opcode),
+        };
+        self.undefined_policy = policy;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A machine code call, an opcode no variant defines, then V0 = 1
+    const ROM: [u8; 6] = [0x03, 0x45, 0x50, 0x0F, 0x60, 0x01];
+
+    fn chip8(policy: UndefinedPolicy) -> Chip8 {
+        let mut chip8 = Chip8::new();
+        chip8.set_undefined_policy(policy);
+        chip8.load(&ROM).unwrap();
+        chip8
+    }
+
+    #[test]
+    fn halt() {
+        let mut chip8 = chip8(UndefinedPolicy::Halt);
+        let err = chip8.emulate().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UndefinedInstruction);
+        assert_eq!(err.opcode(), Some(0x0345));
+    }
+
+    #[test]
+    fn ignore() {
+        let mut chip8 = chip8(UndefinedPolicy::Ignore);
+        for _ in 0..3 {
+            chip8.emulate().unwrap();
+        }
+        assert_eq!(chip8.register(0), 1);
+        assert_eq!(chip8.pc(), 0x206);
+    }
+
+    #[test]
+    fn trap() {
+        // Emulates the 0345 routine by setting VF to the PC, and rejects anything else
+        let mut chip8 = chip8(UndefinedPolicy::trap(|chip8, opcode| {
+            if opcode != 0x0345 {
+                return Err(ErrorKind::UndefinedInstruction);
+            }
+            chip8.set_register(0xF, chip8.pc() as u8);
+            Ok(())
+        }));
+
+        chip8.emulate().unwrap();
+        assert_eq!(chip8.register(0xF), 0x02);
+
+        let err = chip8.emulate().unwrap_err();
+        assert_eq!(err.opcode(), Some(0x500F));
+
+        // The handler is kept across resets
+        chip8.reset();
+        chip8.load(&ROM).unwrap();
+        chip8.emulate().unwrap();
+        assert!(matches!(chip8.undefined_policy(), UndefinedPolicy::Trap(_)));
+    }
+}
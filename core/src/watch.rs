@@ -0,0 +1,229 @@
+use crate::Chip8;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Fires when an instruction reads or writes memory inside an address range.
+///
+/// Only data accesses are watched: sprite reads by `Dxyn`, register loads and stores, BCD
+/// writes and the XO-CHIP audio pattern, but not instruction fetches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    start: u16,
+    end: u16,
+    read: bool,
+    write: bool,
+    pause: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A memory access that triggered a [`Watchpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// Address of the instruction that made the access.
+    pub pc: u16,
+    pub opcode: u16,
+    pub addr: u16,
+    pub access: Access,
+    /// The value in memory before the access.
+    pub old: u8,
+    /// The value in memory after the access, the same as `old` for reads.
+    pub new: u8,
+    /// Whether the watchpoint asks to pause emulation.
+    pub pause: bool,
+}
+
+impl Watchpoint {
+    /// Watches reads inside `range`.
+    #[must_use]
+    pub const fn read(range: RangeInclusive<u16>) -> Self {
+        Self::new(range, true, false)
+    }
+
+    /// Watches writes inside `range`.
+    #[must_use]
+    pub const fn write(range: RangeInclusive<u16>) -> Self {
+        Self::new(range, false, true)
+    }
+
+    /// Watches both reads and writes inside `range`.
+    #[must_use]
+    pub const fn access(range: RangeInclusive<u16>) -> Self {
+        Self::new(range, true, true)
+    }
+
+    const fn new(range: RangeInclusive<u16>, read: bool, write: bool) -> Self {
+        Self {
+            start: *range.start(),
+            end: *range.end(),
+            read,
+            write,
+            pause: true,
+        }
+    }
+
+    /// Sets whether a hit pauses emulation, which is the default.
+    #[must_use]
+    pub const fn with_pause(mut self, pause: bool) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    #[must_use]
+    pub const fn range(&self) -> RangeInclusive<u16> {
+        self.start..=self.end
+    }
+
+    #[must_use]
+    pub const fn pause(&self) -> bool {
+        self.pause
+    }
+
+    #[must_use]
+    pub const fn matches(&self, addr: u16, access: Access) -> bool {
+        let watched = match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+        };
+        watched && self.start <= addr && addr <= self.end
+    }
+}
+
+impl Chip8 {
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        if !self.watchpoints.contains(&watchpoint) {
+            self.watchpoints.push(watchpoint);
+        }
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.retain(|&other| other != watchpoint);
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    #[must_use]
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Returns the watched accesses made by the last executed instruction.
+    #[must_use]
+    pub fn watch_hits(&self) -> &[WatchHit] {
+        &self.watch_hits
+    }
+
+    /// Returns the first hit of the last executed instruction that asks to pause emulation.
+    #[must_use]
+    pub fn watch_pause(&self) -> Option<WatchHit> {
+        self.watch_hits.iter().find(|hit| hit.pause).copied()
+    }
+
+    pub(crate) fn watch(&mut self, addr: usize, access: Access, old: u8, new: u8) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        let addr = addr as u16;
+        let hits = self
+            .watchpoints
+            .iter()
+            .filter(|watchpoint| watchpoint.matches(addr, access));
+        if let Some(pause) = hits.map(Watchpoint::pause).reduce(|a, b| a || b) {
+            self.watch_hits.push(WatchHit {
+                pc: self.instruction_pc,
+                opcode: self.opcode,
+                addr,
+                access,
+                old,
+                new,
+                pause,
+            });
+        }
+    }
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let access = match self.access {
+            Access::Read => "Read",
+            Access::Write => "Write",
+        };
+        write!(
+            f,
+            "{access} {:#05x} by {:04X} at {:#05x}: {:02X} -> {:02X}",
+            self.addr, self.opcode, self.pc, self.old, self.new
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(watchpoint: Watchpoint, program: &[u16]) -> Chip8 {
+        let rom: Vec<u8> = program.iter().flat_map(|op| op.to_be_bytes()).collect();
+        let mut chip8 = Chip8::new();
+        chip8.load(&rom).unwrap();
+        chip8.add_watchpoint(watchpoint);
+        for _ in program {
+            chip8.emulate().unwrap();
+        }
+        chip8
+    }
+
+    // Sorted by address, as the order of the accesses within an instruction isn't specified
+    fn values(chip8: &Chip8) -> Vec<(u16, Access, u8, u8)> {
+        let mut values: Vec<_> = chip8
+            .watch_hits()
+            .iter()
+            .map(|hit| (hit.addr, hit.access, hit.old, hit.new))
+            .collect();
+        values.sort_by_key(|&(addr, ..)| addr);
+        values
+    }
+
+    #[test]
+    fn bcd() {
+        let chip8 = run(Watchpoint::write(0x301..=0x302), &[0xA300, 0x607B, 0xF033]);
+        assert_eq!(
+            values(&chip8),
+            [(0x301, Access::Write, 0, 2), (0x302, Access::Write, 0, 3)]
+        );
+
+        let hit = chip8.watch_pause().unwrap();
+        assert_eq!((hit.pc, hit.opcode), (0x204, 0xF033));
+    }
+
+    #[test]
+    fn store_and_load() {
+        let store = [0xA300, 0x6005, 0x6106, 0xF155];
+        let chip8 = run(Watchpoint::access(0x300..=0x3FF), &store);
+        assert_eq!(
+            values(&chip8),
+            [(0x300, Access::Write, 0, 5), (0x301, Access::Write, 0, 6)]
+        );
+
+        // Writes don't trigger a read watchpoint
+        let chip8 = run(Watchpoint::read(0x300..=0x3FF), &store);
+        assert!(chip8.watch_hits().is_empty());
+
+        let load = [0xA300, 0x6005, 0xF055, 0xA300, 0xF065];
+        let chip8 = run(Watchpoint::read(0x300..=0x3FF), &load);
+        assert_eq!(values(&chip8), [(0x300, Access::Read, 5, 5)]);
+    }
+
+    #[test]
+    fn sprite() {
+        let watchpoint = Watchpoint::read(0x302..=0x302).with_pause(false);
+        let chip8 = run(watchpoint, &[0xA300, 0xD015]);
+        assert_eq!(values(&chip8), [(0x302, Access::Read, 0, 0)]);
+        assert_eq!(chip8.watch_pause(), None);
+    }
+}
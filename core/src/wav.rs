@@ -0,0 +1,127 @@
+use crate::{AudioRenderer, Chip8};
+use std::io::{self, Write};
+
+const CHANNELS: u16 = 1;
+const BITS_PER_SAMPLE: u16 = 16;
+const BYTES_PER_SAMPLE: u32 = (BITS_PER_SAMPLE / 8) as u32;
+const HEADER_SIZE: u32 = 44;
+
+/// Records the buzzer frame by frame and encodes it as a 16-bit PCM WAV file.
+///
+/// The recording only depends on the emulated frames, so a headless run produces the same
+/// audio regardless of how fast it executes.
+#[derive(Debug, Clone)]
+pub struct WavRecorder {
+    renderer: AudioRenderer,
+    samples: Vec<i16>,
+}
+
+impl WavRecorder {
+    #[must_use]
+    pub const fn new(renderer: AudioRenderer) -> Self {
+        Self {
+            renderer,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Appends the sound of one 60 Hz timer frame. Call it once per frame, after the timers
+    /// are ticked.
+    pub fn record_frame(&mut self, chip8: &Chip8) {
+        let frame = self.renderer.render_frame(chip8);
+        self.samples.extend(
+            frame
+                .into_iter()
+                .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+        );
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Returns the recorded length in seconds.
+    #[must_use]
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.renderer.sample_rate() as f32
+    }
+
+    /// Writes the recording as a WAV file.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let sample_rate = self.renderer.sample_rate();
+        let data_size = u32::try_from(self.samples.len())
+            .ok()
+            .and_then(|len| len.checked_mul(BYTES_PER_SAMPLE))
+            .filter(|&size| size <= u32::MAX - HEADER_SIZE)
+            .ok_or_else(|| io::Error::other("Recording is too long for a WAV file"))?;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        // Integer PCM
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&CHANNELS.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * CHANNELS as u32 * BYTES_PER_SAMPLE).to_le_bytes())?;
+        writer.write_all(&(CHANNELS * BITS_PER_SAMPLE / 8).to_le_bytes())?;
+        writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+        for sample in &self.samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let mut recorder = WavRecorder::new(AudioRenderer::new(6_000));
+        let chip8 = Chip8::new();
+        recorder.record_frame(&chip8);
+        recorder.record_frame(&chip8);
+        assert_eq!(recorder.samples().len(), 200);
+        assert_eq!(recorder.duration(), 200.0 / 6_000.0);
+
+        let mut wav = Vec::new();
+        recorder.write_to(&mut wav).unwrap();
+        assert_eq!(wav.len(), 44 + 400);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(wav[4..8], (36u32 + 400).to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(wav[22..24], 1u16.to_le_bytes());
+        assert_eq!(wav[24..28], 6_000u32.to_le_bytes());
+        assert_eq!(wav[28..32], 12_000u32.to_le_bytes());
+        assert_eq!(wav[34..36], 16u16.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(wav[40..44], 400u32.to_le_bytes());
+        assert!(wav[44..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn samples() {
+        let mut chip8 = Chip8::new();
+        chip8.set_sound_timer(1);
+        chip8.tick_timers();
+
+        let mut recorder = WavRecorder::new(AudioRenderer::new(6_000).with_volume(1.0));
+        recorder.record_frame(&chip8);
+        assert_eq!(recorder.samples()[0], i16::MAX);
+        assert!(recorder.samples().contains(&-i16::MAX));
+
+        // The sound timer has run out, so the next frame is silent
+        chip8.tick_timers();
+        recorder.record_frame(&chip8);
+        assert!(recorder.samples()[100..].iter().all(|&sample| sample == 0));
+    }
+}
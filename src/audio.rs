@@ -0,0 +1,46 @@
+use chip8_core::{AudioRenderer, Chip8};
+use rodio::buffer::SamplesBuffer;
+use rodio::{OutputStream, OutputStreamBuilder, Sink};
+
+// Frames queued ahead of the device before new ones are dropped, to bound the latency
+const MAX_QUEUED_FRAMES: usize = 4;
+
+/// Plays the buzzer on the default output device, one timer frame at a time.
+pub struct Speaker {
+    // Playback stops when the stream is dropped
+    _stream: OutputStream,
+    sink: Sink,
+    renderer: AudioRenderer,
+}
+
+impl Speaker {
+    /// Opens the default output device, or returns `None` when there is no usable device.
+    pub fn new() -> Option<Self> {
+        let mut stream = OutputStreamBuilder::open_default_stream().ok()?;
+        stream.log_on_drop(false);
+
+        let sink = Sink::connect_new(stream.mixer());
+        let renderer = AudioRenderer::new(stream.config().sample_rate());
+
+        Some(Self {
+            _stream: stream,
+            sink,
+            renderer,
+        })
+    }
+
+    /// Queues the sound of the current timer frame.
+    pub fn play_frame(&mut self, chip8: &Chip8) {
+        if self.sink.len() >= MAX_QUEUED_FRAMES {
+            return;
+        }
+
+        let samples = self.renderer.render_frame(chip8);
+        self.sink
+            .append(SamplesBuffer::new(1, self.renderer.sample_rate(), samples));
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.sink.set_volume(if muted { 0.0 } else { 1.0 });
+    }
+}
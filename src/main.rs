@@ -1,16 +1,25 @@
 #![allow(clippy::cast_lossless)]
 
-use chip8_core::{Chip8, VIDEO_HEIGHT, VIDEO_WIDTH};
+mod audio;
+
+use audio::Speaker;
+use chip8_core::{
+    AudioRenderer, Chip8, Debugger, ExecuteError, LORES_HEIGHT, LORES_WIDTH, LoadError, Movie,
+    MovieError, Platform, RewindBuffer, StateError, StopReason, Watchpoint, WavRecorder,
+};
 use iced::alignment::Vertical;
 use iced::keyboard;
 use iced::widget::image::{FilterMethod, Handle};
 use iced::widget::space::horizontal;
-use iced::widget::{Button, Checkbox, button, checkbox, column as col, container, image, text};
+use iced::widget::{
+    Button, Checkbox, button, checkbox, column as col, container, image, row, text, text_input,
+};
 use iced::window;
-use iced::{Color, Element, Length, Size, Subscription, Task};
+use iced::{Color, Element, Font, Length, Size, Subscription, Task};
 use iced_aw::menu::DrawPath;
 use rfd::AsyncFileDialog;
 use std::io;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
@@ -20,7 +29,25 @@ type MenuBar<'a, Message> = iced_aw::menu::MenuBar<'a, Message, iced::Theme, ice
 
 const VIDEO_SCALE: f32 = 10.0;
 
-const TIMER_HZ: u32 = 60;
+// About 500 instructions per second at 60 frames per second
+const INSTRUCTIONS_PER_FRAME: u32 = 8;
+
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// Frames run at once to catch up after a slow refresh, older ones are dropped
+const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+const STATE_EXTENSION: &str = "c8s";
+
+const MOVIE_EXTENSION: &str = "c8m";
+
+const WAV_SAMPLE_RATE: u32 = 44_100;
+
+const DEBUGGER_WIDTH: f32 = 220.0;
+
+// A snapshot every frame so that rewinding plays back smoothly, several minutes fit the budget
+const REWIND_INTERVAL: u32 = 1;
+const REWIND_BUDGET: usize = 16 * 1024 * 1024;
 
 fn main() -> iced::Result {
     iced::application(App::default, App::update, App::view)
@@ -28,8 +55,8 @@ fn main() -> iced::Result {
         .subscription(App::subscription)
         .window(window::Settings {
             size: Size::new(
-                VIDEO_WIDTH as f32 * VIDEO_SCALE,
-                VIDEO_HEIGHT as f32 * VIDEO_SCALE + 30.0,
+                LORES_WIDTH as f32 * VIDEO_SCALE,
+                LORES_HEIGHT as f32 * VIDEO_SCALE + 30.0,
             ),
             min_size: Some(Size::new(180.0, 180.0)),
             ..Default::default()
@@ -42,21 +69,90 @@ enum Message {
     SelectRom,
     RomSelected(Option<PathBuf>),
     RomLoaded(Result<Vec<u8>, io::ErrorKind>),
+    SaveState,
+    FileSaved(Result<(), io::ErrorKind>),
+    LoadState,
+    StateSelected(Option<PathBuf>),
+    StateLoaded(Result<Vec<u8>, io::ErrorKind>),
     KeyPressed(String),
     KeyReleased(String),
     PauseToggled(bool),
+    PlatformSelected(Platform),
+    MuteToggled(bool),
+    RewindHeld(bool),
+    RecordingToggled,
+    MovieRecordingToggled,
+    MoviePlaybackToggled,
+    MovieSelected(Option<PathBuf>),
+    MovieLoaded(Result<Vec<u8>, io::ErrorKind>),
+    DebuggerToggled(bool),
+    Debug(DebugAction),
+    BreakpointToggled,
+    WatchInputChanged(String),
+    WatchpointAdded,
+    WatchpointsCleared,
     Stop,
-    EmulateTick,
-    TimerTick,
+    Reset,
+    Continue,
+    SaveReport,
+    ErrorDismissed,
+    Frame(Instant),
     Exit,
 }
 
+#[derive(Debug, Clone, Copy)]
+enum DebugAction {
+    StepInto,
+    StepOver,
+    StepOut,
+    StepBack,
+    RunToFrame,
+}
+
 struct App {
     emulator: Chip8,
-    clock_speed: u32,
+    // The loaded ROM, kept to restart it on reset
+    rom: Vec<u8>,
+    // The quirk profile applied whenever a ROM is (re)loaded
+    platform: Platform,
+    debugger: Debugger,
+    rewind: RewindBuffer,
+    speaker: Option<Speaker>,
+    recorder: Option<WavRecorder>,
+    movie: Option<Movie>,
+    // The movie being replayed and the frame it is at
+    playback: Option<(Movie, usize)>,
     is_loaded: bool,
     is_paused: bool,
-    error: Option<io::ErrorKind>,
+    last_frame: Option<Instant>,
+    frame_lag: Duration,
+    is_muted: bool,
+    is_rewinding: bool,
+    show_debugger: bool,
+    stop_reason: Option<StopReason>,
+    watch_input: String,
+    error: Option<Error>,
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(io::ErrorKind),
+    Load(LoadError),
+    State(StateError),
+    Execute(ExecuteError),
+    Movie(MovieError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(kind) => write!(f, "{}", io::Error::from(*kind)),
+            Self::Load(err) => write!(f, "{err}"),
+            Self::State(err) => write!(f, "{err}"),
+            Self::Execute(err) => write!(f, "Emulation failed: {err}"),
+            Self::Movie(err) => write!(f, "{err}"),
+        }
+    }
 }
 
 impl Default for App {
@@ -67,12 +163,27 @@ impl Default for App {
 
 impl App {
     fn new() -> Self {
-        let emulator = Chip8::new();
+        let platform = Platform::CosmacVip;
+        let emulator = Chip8::with_quirks(platform.quirks());
         Self {
             emulator,
-            clock_speed: 500,
+            rom: Vec::new(),
+            platform,
+            debugger: Debugger::new(INSTRUCTIONS_PER_FRAME),
+            rewind: RewindBuffer::new(REWIND_INTERVAL, REWIND_BUDGET),
+            speaker: Speaker::new(),
+            recorder: None,
+            movie: None,
+            playback: None,
             is_loaded: false,
             is_paused: false,
+            last_frame: None,
+            frame_lag: Duration::ZERO,
+            is_muted: false,
+            is_rewinding: false,
+            show_debugger: false,
+            stop_reason: None,
+            watch_input: String::new(),
             error: None,
         }
     }
@@ -95,13 +206,64 @@ impl App {
                 if self.is_loaded {
                     self.emulator.reset();
                 }
-                self.emulator.load(&rom);
+                self.emulator.set_quirks(self.platform.quirks());
+                if let Err(err) = self.emulator.load(&rom) {
+                    self.is_loaded = false;
+                    self.error = Some(Error::Load(err));
+                    return Task::none();
+                }
+                self.rom = rom;
+                self.rewind.clear();
+                self.end_movie();
+                self.debugger.clear_breakpoints();
                 self.is_loaded = true;
                 self.is_paused = false;
+                self.stop_reason = None;
+                self.error = None;
                 Task::none()
             }
             Message::RomLoaded(Err(err)) => {
-                self.error = Some(err);
+                self.error = Some(Error::Io(err));
+                Task::none()
+            }
+            Message::SaveState => Task::perform(
+                save_file(
+                    "Save State",
+                    ("Save state", STATE_EXTENSION),
+                    self.emulator.save_state(),
+                ),
+                Message::FileSaved,
+            ),
+            Message::FileSaved(result) => {
+                if let Err(err) = result {
+                    self.error = Some(Error::Io(err));
+                }
+                Task::none()
+            }
+            Message::LoadState => Task::perform(pick_state_file(), Message::StateSelected),
+            Message::StateSelected(path) => {
+                if let Some(path) = path {
+                    Task::perform(load_file(path), Message::StateLoaded)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::StateLoaded(Ok(state)) => {
+                match self.emulator.load_state(&state) {
+                    Ok(()) => {
+                        self.rewind.clear();
+                        self.end_movie();
+                    }
+                    Err(err) => self.error = Some(Error::State(err)),
+                }
+                Task::none()
+            }
+            Message::StateLoaded(Err(err)) => {
+                self.error = Some(Error::Io(err));
+                Task::none()
+            }
+            // The keypad follows the movie during playback
+            Message::KeyPressed(_) | Message::KeyReleased(_) if self.playback.is_some() => {
                 Task::none()
             }
             Message::KeyPressed(key) => {
@@ -118,24 +280,214 @@ impl App {
             }
             Message::PauseToggled(checked) => {
                 self.is_paused = checked;
+                self.last_frame = None;
+                Task::none()
+            }
+            Message::PlatformSelected(platform) => {
+                self.platform = platform;
+                if self.is_loaded {
+                    // The ROM may not fit or behave differently, so it starts over
+                    return self.update(Message::Reset);
+                }
+                self.emulator.set_quirks(platform.quirks());
+                Task::none()
+            }
+            Message::MuteToggled(checked) => {
+                self.is_muted = checked;
+                if let Some(speaker) = &self.speaker {
+                    speaker.set_muted(checked);
+                }
+                Task::none()
+            }
+            Message::RecordingToggled => {
+                if let Some(recorder) = self.recorder.take() {
+                    let mut wav = Vec::new();
+                    match recorder.write_to(&mut wav) {
+                        Ok(()) => {
+                            return Task::perform(
+                                save_file("Save Recording", ("WAV audio", "wav"), wav),
+                                Message::FileSaved,
+                            );
+                        }
+                        Err(err) => self.error = Some(Error::Io(err.kind())),
+                    }
+                } else {
+                    let renderer = AudioRenderer::new(WAV_SAMPLE_RATE);
+                    self.recorder = Some(WavRecorder::new(renderer));
+                }
+                Task::none()
+            }
+            Message::RewindHeld(held) => {
+                // Rewinding would break the movie being recorded or replayed
+                self.is_rewinding = held && self.movie.is_none() && self.playback.is_none();
+                Task::none()
+            }
+            Message::MovieRecordingToggled => {
+                if let Some(movie) = self.movie.take() {
+                    return Task::perform(
+                        save_file("Save Movie", ("Movie", MOVIE_EXTENSION), movie.to_bytes()),
+                        Message::FileSaved,
+                    );
+                }
+
+                self.end_movie();
+                let seed = self.emulator.seed().unwrap_or_default();
+                match Movie::record(&mut self.emulator, &self.rom, seed, INSTRUCTIONS_PER_FRAME) {
+                    Ok(movie) => {
+                        self.movie = Some(movie);
+                        self.restart();
+                    }
+                    Err(err) => {
+                        self.is_loaded = false;
+                        self.error = Some(Error::Load(err));
+                    }
+                }
+                Task::none()
+            }
+            Message::MoviePlaybackToggled => {
+                if self.playback.is_some() {
+                    self.end_movie();
+                    Task::none()
+                } else {
+                    Task::perform(pick_movie_file(), Message::MovieSelected)
+                }
+            }
+            Message::MovieSelected(path) => {
+                if let Some(path) = path {
+                    Task::perform(load_file(path), Message::MovieLoaded)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::MovieLoaded(Ok(data)) => {
+                let movie = Movie::from_bytes(&data)
+                    .and_then(|movie| movie.start(&mut self.emulator, &self.rom).map(|()| movie));
+                match movie {
+                    Ok(movie) => {
+                        self.end_movie();
+                        self.debugger
+                            .set_instructions_per_frame(movie.instructions_per_frame());
+                        self.playback = Some((movie, 0));
+                        self.restart();
+                    }
+                    Err(err) => self.error = Some(Error::Movie(err)),
+                }
+                Task::none()
+            }
+            Message::MovieLoaded(Err(err)) => {
+                self.error = Some(Error::Io(err));
+                Task::none()
+            }
+            Message::DebuggerToggled(checked) => {
+                self.show_debugger = checked;
+                Task::none()
+            }
+            Message::BreakpointToggled => {
+                self.debugger.toggle_breakpoint(self.emulator.pc());
+                Task::none()
+            }
+            Message::WatchInputChanged(input) => {
+                self.watch_input = input;
+                Task::none()
+            }
+            Message::WatchpointAdded => {
+                if let Some(range) = parse_addr_range(&self.watch_input) {
+                    self.emulator.add_watchpoint(Watchpoint::access(range));
+                    self.watch_input.clear();
+                }
+                Task::none()
+            }
+            Message::WatchpointsCleared => {
+                self.emulator.clear_watchpoints();
+                Task::none()
+            }
+            Message::Debug(action) => {
+                if self.is_loaded && self.is_paused {
+                    let emulator = &mut self.emulator;
+                    let result = match action {
+                        DebugAction::StepInto => self.debugger.step_into(emulator),
+                        DebugAction::StepOver => self.debugger.step_over(emulator),
+                        DebugAction::StepOut => self.debugger.step_out(emulator),
+                        DebugAction::StepBack => {
+                            match self.debugger.step_back(emulator, &mut self.rewind) {
+                                Ok(true) => Ok(StopReason::Step),
+                                // The rewind buffer doesn't reach back any further
+                                Ok(false) => return Task::none(),
+                                Err(err) => Err(err),
+                            }
+                        }
+                        DebugAction::RunToFrame => self.debugger.run_until_frame(emulator),
+                    };
+                    match result {
+                        Ok(reason) => self.stop_reason = Some(reason),
+                        Err(err) => self.fail(err),
+                    }
+                }
                 Task::none()
             }
             Message::Stop => {
                 self.is_loaded = false;
                 self.is_paused = false;
+                self.error = None;
                 self.emulator.reset();
+                self.rewind.clear();
+                self.end_movie();
                 Task::none()
             }
-            Message::EmulateTick => {
-                if self.is_loaded {
-                    self.emulator
-                        .emulate()
-                        .expect("Failed while emulating Chip8 instruction");
+            Message::Reset => {
+                self.emulator.reset();
+                self.emulator.set_quirks(self.platform.quirks());
+                if let Err(err) = self.emulator.load(&self.rom) {
+                    self.is_loaded = false;
+                    self.error = Some(Error::Load(err));
+                    return Task::none();
+                }
+                self.end_movie();
+                self.restart();
+                Task::none()
+            }
+            Message::Continue => {
+                self.is_paused = false;
+                self.last_frame = None;
+                self.error = None;
+                Task::none()
+            }
+            Message::SaveReport => {
+                let Some(Error::Execute(err)) = &self.error else {
+                    return Task::none();
+                };
+                let mut report = Vec::new();
+                match err.write_report(&mut report) {
+                    Ok(()) => Task::perform(
+                        save_file("Save Crash Report", ("Text", "txt"), report),
+                        Message::FileSaved,
+                    ),
+                    Err(err) => {
+                        self.error = Some(Error::Io(err.kind()));
+                        Task::none()
+                    }
                 }
+            }
+            Message::ErrorDismissed => {
+                self.error = None;
                 Task::none()
             }
-            Message::TimerTick => {
-                self.emulator.tick_timers();
+            Message::Frame(now) => {
+                let elapsed = self
+                    .last_frame
+                    .map_or(FRAME_DURATION, |last| now.saturating_duration_since(last));
+                self.last_frame = Some(now);
+                self.frame_lag =
+                    (self.frame_lag + elapsed).min(FRAME_DURATION * MAX_CATCH_UP_FRAMES);
+
+                while self.is_loaded && !self.is_paused && self.frame_lag >= FRAME_DURATION {
+                    self.frame_lag -= FRAME_DURATION;
+                    if self.is_rewinding {
+                        self.rewind.rewind(&mut self.emulator, 1);
+                    } else {
+                        self.run_frame();
+                    }
+                }
                 Task::none()
             }
             Message::Exit => window::latest().and_then(window::close),
@@ -148,6 +500,16 @@ impl App {
                 menu_header("File"),
                 menu(vec![
                     Item::new(menu_item("Open").on_press(Message::SelectRom)),
+                    Item::new(menu_item("Save State").on_press_maybe(if self.is_loaded {
+                        Some(Message::SaveState)
+                    } else {
+                        None
+                    })),
+                    Item::new(menu_item("Load State").on_press_maybe(if self.is_loaded {
+                        Some(Message::LoadState)
+                    } else {
+                        None
+                    })),
                     Item::new(menu_item("Exit").on_press(Message::Exit)),
                 ]),
             ),
@@ -166,6 +528,57 @@ impl App {
                     } else {
                         None
                     })),
+                    Item::new(
+                        menu_item("Reset").on_press_maybe(self.is_loaded.then_some(Message::Reset)),
+                    ),
+                    Item::with_menu(
+                        menu_item("Platform"),
+                        menu(
+                            Platform::ALL
+                                .into_iter()
+                                .map(|platform| {
+                                    Item::new(
+                                        menu_checkbox(platform.name(), self.platform == platform)
+                                            .on_toggle(move |_| {
+                                                Message::PlatformSelected(platform)
+                                            }),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    ),
+                    Item::new(menu_checkbox("Mute", self.is_muted).on_toggle(Message::MuteToggled)),
+                    Item::new(
+                        menu_item(if self.recorder.is_some() {
+                            "Stop Recording"
+                        } else {
+                            "Record Audio"
+                        })
+                        .on_press_maybe(
+                            (self.is_loaded || self.recorder.is_some())
+                                .then_some(Message::RecordingToggled),
+                        ),
+                    ),
+                    Item::new(
+                        menu_item(if self.movie.is_some() {
+                            "Stop Movie"
+                        } else {
+                            "Record Movie"
+                        })
+                        .on_press_maybe(self.is_loaded.then_some(Message::MovieRecordingToggled)),
+                    ),
+                    Item::new(
+                        menu_item(if self.playback.is_some() {
+                            "Stop Playback"
+                        } else {
+                            "Play Movie"
+                        })
+                        .on_press_maybe(self.is_loaded.then_some(Message::MoviePlaybackToggled)),
+                    ),
+                    Item::new(
+                        menu_checkbox("Debugger", self.show_debugger)
+                            .on_toggle(Message::DebuggerToggled),
+                    ),
                 ]),
             ),
         ])
@@ -174,16 +587,229 @@ impl App {
 
         let pixels = convert_to_rgba(self.emulator.framebuffer());
         let screen = image(Handle::from_rgba(
-            VIDEO_WIDTH as u32,
-            VIDEO_HEIGHT as u32,
+            self.emulator.width() as u32,
+            self.emulator.height() as u32,
             pixels,
         ))
         .width(Length::Fill)
         .height(Length::Fill)
         .filter_method(FilterMethod::Nearest);
 
-        container(col![menu_bar, horizontal().height(5), screen])
-            .style(|_| container::Style::from(Color::BLACK))
+        let error = self.error.as_ref().map(|err| self.status_bar(err));
+
+        let debugger = self.show_debugger.then(|| self.debugger_panel());
+
+        container(col![
+            menu_bar,
+            horizontal().height(5),
+            row![screen, debugger],
+            error
+        ])
+        .style(|_| container::Style::from(Color::BLACK))
+        .into()
+    }
+
+    fn run_frame(&mut self) {
+        if let Some((movie, frame)) = &self.playback {
+            movie.apply_frame(&mut self.emulator, *frame);
+        }
+
+        let reason = match self.debugger.run_frame(&mut self.emulator) {
+            Ok(reason) => reason,
+            Err(err) => {
+                self.fail(err);
+                return;
+            }
+        };
+
+        if reason != StopReason::FrameEnd {
+            self.stop(reason);
+            return;
+        }
+
+        self.rewind.record_frame(&self.emulator);
+        if let Some(movie) = &mut self.movie {
+            movie.record_frame(&self.emulator);
+        }
+        if let Some((movie, frame)) = &mut self.playback {
+            *frame += 1;
+            if *frame == movie.len() {
+                if let Err(err) = movie.verify(&self.emulator) {
+                    self.error = Some(Error::Movie(err));
+                }
+                self.end_movie();
+            }
+        }
+        if let Some(speaker) = &mut self.speaker {
+            speaker.play_frame(&self.emulator);
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(&self.emulator);
+        }
+    }
+
+    fn stop(&mut self, reason: StopReason) {
+        self.is_paused = true;
+        self.last_frame = None;
+        self.show_debugger = true;
+        self.stop_reason = Some(reason);
+    }
+
+    /// Resumes emulation from the start of the loaded ROM, after the machine was reset.
+    fn restart(&mut self) {
+        self.rewind.clear();
+        self.is_paused = false;
+        self.last_frame = None;
+        self.stop_reason = None;
+        self.error = None;
+    }
+
+    /// Drops the movie being recorded or replayed.
+    fn end_movie(&mut self) {
+        self.movie = None;
+        if self.playback.take().is_some() {
+            self.debugger
+                .set_instructions_per_frame(INSTRUCTIONS_PER_FRAME);
+        }
+    }
+
+    /// Pauses after an instruction failed and reports the error in the status bar.
+    fn fail(&mut self, err: ExecuteError) {
+        self.is_paused = true;
+        self.last_frame = None;
+        self.stop_reason = None;
+        self.error = Some(Error::Execute(err));
+    }
+
+    fn status_bar(&self, err: &Error) -> Element<'_, Message> {
+        let message = text(err.to_string())
+            .size(14)
+            .color(Color::from_rgb8(0xFF, 0x55, 0x55));
+
+        let actions = if matches!(err, Error::Execute(_)) {
+            row![
+                status_button("Reset").on_press(Message::Reset),
+                status_button("Continue").on_press(Message::Continue),
+                status_button("Debugger").on_press(Message::DebuggerToggled(true)),
+                status_button("Save Report").on_press(Message::SaveReport),
+            ]
+        } else {
+            row![status_button("Dismiss").on_press(Message::ErrorDismissed)]
+        };
+
+        container(
+            row![message, horizontal(), actions.spacing(5)]
+                .spacing(10)
+                .align_y(Vertical::Center),
+        )
+        .padding(5)
+        .width(Length::Fill)
+        .into()
+    }
+
+    fn debugger_panel(&self) -> Element<'_, Message> {
+        let pc = self.emulator.pc();
+        let marker = if self.debugger.has_breakpoint(pc) {
+            "*"
+        } else {
+            " "
+        };
+        let stop_reason = self
+            .stop_reason
+            .map_or_else(|| String::from("Running"), |reason| reason.to_string());
+        let breakpoints = self
+            .debugger
+            .breakpoints()
+            .map(|addr| format!("{addr:#05x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let watchpoints = self
+            .emulator
+            .watchpoints()
+            .iter()
+            .map(|watchpoint| {
+                let range = watchpoint.range();
+                format!("{:#05x}-{:#05x}", range.start(), range.end())
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let can_step = self.is_loaded && self.is_paused;
+        let step = |label, action| {
+            debug_button(label).on_press_maybe(can_step.then_some(Message::Debug(action)))
+        };
+
+        let controls = col![
+            row![
+                step("Step Into", DebugAction::StepInto),
+                step("Step Over", DebugAction::StepOver),
+            ]
+            .spacing(5),
+            row![
+                step("Step Out", DebugAction::StepOut),
+                step("Step Back", DebugAction::StepBack),
+            ]
+            .spacing(5),
+            row![step("Run to Frame", DebugAction::RunToFrame)].spacing(5),
+            row![
+                debug_button("Breakpoint")
+                    .on_press_maybe(self.is_loaded.then_some(Message::BreakpointToggled)),
+                debug_button("Continue")
+                    .on_press_maybe(can_step.then_some(Message::PauseToggled(false))),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+
+        let registers = self.emulator.registers();
+        let v = registers.v.chunks(4).enumerate().map(|(row, values)| {
+            let line = values
+                .iter()
+                .enumerate()
+                .map(|(col, value)| format!("V{:X} {value:02X}", row * 4 + col))
+                .collect::<Vec<_>>()
+                .join("  ");
+            debug_text(line).into()
+        });
+        let stack = registers
+            .call_stack()
+            .iter()
+            .map(|addr| format!("{addr:#05x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let info = col![
+            debug_text(format!(
+                "{marker}{pc:#05x}  {}",
+                Debugger::next_instruction(&self.emulator)
+            )),
+            debug_text(stop_reason),
+            col(v),
+            debug_text(format!(
+                "I  {:#05x}  DT {:02X}  ST {:02X}",
+                registers.index, registers.delay_timer, registers.sound_timer
+            )),
+            debug_text(format!("Stack: {stack}")),
+            debug_text(format!("Breakpoints: {breakpoints}")),
+            debug_text(format!("Watchpoints: {watchpoints}")),
+        ]
+        .spacing(5);
+
+        let watch = row![
+            text_input("300-302", &self.watch_input)
+                .size(12)
+                .on_input(Message::WatchInputChanged)
+                .on_submit(Message::WatchpointAdded),
+            debug_button("Watch").on_press(Message::WatchpointAdded),
+            debug_button("Clear").on_press(Message::WatchpointsCleared),
+        ]
+        .spacing(5);
+
+        container(col![controls, watch, info].spacing(10))
+            .padding(5)
+            .width(DEBUGGER_WIDTH)
+            .height(Length::Fill)
             .into()
     }
 
@@ -199,15 +825,19 @@ impl App {
                 modifiers: keyboard::Modifiers::NONE,
                 ..
             } => Some(Message::KeyReleased(key.to_string())),
+            keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Backspace),
+                ..
+            } => Some(Message::RewindHeld(true)),
+            keyboard::Event::KeyReleased {
+                key: keyboard::Key::Named(keyboard::key::Named::Backspace),
+                ..
+            } => Some(Message::RewindHeld(false)),
             _ => None,
         })];
 
         if self.is_loaded && !self.is_paused {
-            let emulate = cycles_per_second(self.clock_speed).map(|_| Message::EmulateTick);
-            let timer = cycles_per_second(TIMER_HZ).map(|_| Message::TimerTick);
-
-            subscriptions.push(emulate);
-            subscriptions.push(timer);
+            subscriptions.push(window::frames().map(Message::Frame));
         }
 
         Subscription::batch(subscriptions)
@@ -222,13 +852,69 @@ async fn pick_file() -> Option<PathBuf> {
         .map(PathBuf::from)
 }
 
+async fn pick_state_file() -> Option<PathBuf> {
+    AsyncFileDialog::new()
+        .set_title("Load State")
+        .add_filter("Save state", &[STATE_EXTENSION])
+        .pick_file()
+        .await
+        .map(PathBuf::from)
+}
+
+async fn pick_movie_file() -> Option<PathBuf> {
+    AsyncFileDialog::new()
+        .set_title("Play Movie")
+        .add_filter("Movie", &[MOVIE_EXTENSION])
+        .pick_file()
+        .await
+        .map(PathBuf::from)
+}
+
+async fn save_file(
+    title: &str,
+    (filter, extension): (&str, &str),
+    data: Vec<u8>,
+) -> Result<(), io::ErrorKind> {
+    let Some(file) = AsyncFileDialog::new()
+        .set_title(title)
+        .add_filter(filter, &[extension])
+        .save_file()
+        .await
+    else {
+        return Ok(());
+    };
+    tokio::fs::write(file.path(), data)
+        .await
+        .map_err(|err| err.kind())
+}
+
 async fn load_file(path: impl AsRef<Path>) -> Result<Vec<u8>, io::ErrorKind> {
     tokio::fs::read(path).await.map_err(|err| err.kind())
 }
 
-fn convert_to_rgba(data: &[bool]) -> Vec<u8> {
+// Colors for every combination of the four XO-CHIP planes, indexed by the plane bitmask
+const PALETTE: [Color; 16] = [
+    Color::BLACK,
+    Color::WHITE,
+    Color::from_rgb8(0xFF, 0x66, 0x00),
+    Color::from_rgb8(0x99, 0x99, 0x99),
+    Color::from_rgb8(0x00, 0x99, 0xFF),
+    Color::from_rgb8(0x00, 0xCC, 0x66),
+    Color::from_rgb8(0xCC, 0x00, 0x66),
+    Color::from_rgb8(0x66, 0x33, 0x00),
+    Color::from_rgb8(0xFF, 0xCC, 0x00),
+    Color::from_rgb8(0x66, 0x00, 0xCC),
+    Color::from_rgb8(0x00, 0x66, 0x66),
+    Color::from_rgb8(0xFF, 0x99, 0x99),
+    Color::from_rgb8(0x99, 0xFF, 0x99),
+    Color::from_rgb8(0x99, 0x99, 0xFF),
+    Color::from_rgb8(0x33, 0x33, 0x33),
+    Color::from_rgb8(0xCC, 0xCC, 0x66),
+];
+
+fn convert_to_rgba(data: &[u8]) -> Vec<u8> {
     data.iter()
-        .map(|&pixel| if pixel { Color::WHITE } else { Color::BLACK })
+        .map(|&pixel| PALETTE[(pixel & 0xF) as usize])
         .flat_map(Color::into_rgba8)
         .collect()
 }
@@ -281,6 +967,33 @@ fn menu_checkbox(label: &str, is_checked: bool) -> Checkbox<'_, Message> {
     checkbox(is_checked).label(label).width(Length::Fill)
 }
 
-fn cycles_per_second(hertz: u32) -> Subscription<Instant> {
-    iced::time::every(Duration::from_secs(1) / hertz)
+/// Parses a hexadecimal address or an inclusive `start-end` address range.
+fn parse_addr_range(input: &str) -> Option<RangeInclusive<u16>> {
+    let parse = |addr: &str| {
+        let addr = addr.trim();
+        let addr = addr.strip_prefix("0x").unwrap_or(addr);
+        u16::from_str_radix(addr, 16).ok()
+    };
+
+    match input.split_once('-') {
+        Some((start, end)) => Some(parse(start)?..=parse(end)?),
+        None => parse(input).map(|addr| addr..=addr),
+    }
+}
+
+fn debug_button(label: &str) -> Button<'_, Message> {
+    button(text(label).size(12))
+        .padding([4, 6])
+        .width(Length::Fill)
+}
+
+fn status_button(label: &str) -> Button<'_, Message> {
+    button(text(label).size(12)).padding([4, 6])
+}
+
+fn debug_text<'a>(content: String) -> iced::widget::Text<'a> {
+    text(content)
+        .size(12)
+        .font(Font::MONOSPACE)
+        .color(Color::WHITE)
 }